@@ -1,20 +1,51 @@
-use std::{ffi::OsStr, fs::DirEntry, time::Duration};
+use std::{
+    ffi::OsStr,
+    fs::DirEntry,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::media::decoder;
 
 pub struct Config {}
 
 impl Config {
-    // UI刷新速率 ; 1000ms / 60fps = 16.67ms
+    // 播放中UI定时刷新的间隔 ; 暂停时只在事件到达时刷新
     pub const REFRESH_RATE: Duration = Duration::from_millis(50);
     // 前进后退间隔时间
     pub const FORWARD_AND_REVERSE_STEP: Duration = Duration::from_secs(1);
-    // 支持的文件后缀
-    const ACCEPT_SUFFIX: [&'static str; 4] = ["mp3", "wav", "flac", "ts"];
+    // 歌词偏移调整步长 ; 毫秒
+    pub const LYRIC_OFFSET_STEP: i64 = 100;
+    // 卡拉OK界面切换到下一行歌词的滚动时长
+    pub const KARAOKE_SCROLL_DURATION: Duration = Duration::from_millis(300);
+    // 歌词光标没有操作后恢复跟随播放的时长
+    pub const LYRIC_CURSOR_TIMEOUT: Duration = Duration::from_secs(5);
+    // 导出字幕时，没有歌曲时长的最后一条字幕的时长
+    pub const SUBTITLE_LAST_CUE_DURATION: Duration = Duration::from_secs(5);
+    // 错误信息展示时长
+    pub const ERROR_MESSAGE_DURATION: Duration = Duration::from_secs(5);
+    // 专辑封面文件名和后缀
+    const COVER_NAMES: [&'static str; 4] = ["cover", "folder", "front", "album"];
+    const COVER_SUFFIX: [&'static str; 3] = ["jpg", "jpeg", "png"];
+    // 播放列表最多可以撤销的次数
+    pub const UNDO_LIMIT: usize = 100;
+    // 文件变化后等待这个时长再刷新智能播放列表 ; 合并连续的多次变化
+    pub const SMART_REFRESH_DELAY: Duration = Duration::from_secs(1);
+    // 播放统计修改后等待这个时长再写入 ; 合并连续的多次修改
+    pub const STATS_SAVE_DELAY: Duration = Duration::from_secs(10);
+    // 播放超过一半或超过这个时长后记录一次播放
+    pub const PLAY_COUNT_DURATION: Duration = Duration::from_secs(4 * 60);
+    // 默认保存的播放列表名称
+    pub const DEFAULT_PLAYLIST: &'static str = "default";
+    // 指定图片协议的环境变量 ; kitty | sixel | halfblocks
+    pub const GRAPHICS_PROTOCOL_ENV: &'static str = "RUST_PLAYER_GRAPHICS";
 
     // layout
     pub const LAYOUT_LYRICS_WIDGET_WIDTH: u16 = 30; // 歌词组件宽度
     pub const LAYOUT_LYRICS_WIDGET_WIDTH_ON_FULL_SCREEN: u16 = 50; // 歌词组件全屏时宽度
     pub const LAYOUT_INFO_WIDGET_WIDTH: u16 = 20; // 信息组件宽度
     pub const LAYOUT_INFO_WIDGET_WIDTH_ON_FULL_SCREEN: u16 = 40; // 全屏状态下宽度
+    pub const UP_NEXT_MAX_ROWS: usize = 5; // 下一首播放最多展示的行数
 
     // text
     pub const EMPTY: &'static str = "-"; // 空文本
@@ -26,6 +57,87 @@ impl Config {
     pub const TIP_DIR_IS_NOT_FOUND: &'static str = "目录不存在";
     #[cfg(feature = "en")]
     pub const TIP_DIR_IS_NOT_FOUND: &'static str = "path is not found";
+    #[cfg(feature = "zh")]
+    pub const TIP_PLAY_FAILED: &'static str = "无法播放";
+    #[cfg(feature = "en")]
+    pub const TIP_PLAY_FAILED: &'static str = "cannot play";
+    #[cfg(feature = "zh")]
+    pub const TIP_LYRIC_OFFSET_SAVE_FAILED: &'static str = "无法保存歌词偏移";
+    #[cfg(feature = "en")]
+    pub const TIP_LYRIC_OFFSET_SAVE_FAILED: &'static str = "cannot save lyric offset";
+    #[cfg(feature = "zh")]
+    pub const TIP_LYRIC_SAVE_FAILED: &'static str = "无法保存歌词";
+    #[cfg(feature = "en")]
+    pub const TIP_LYRIC_SAVE_FAILED: &'static str = "cannot save lyrics";
+
+    #[cfg(feature = "zh")]
+    pub const TIP_SCANNING: &'static str = "正在添加文件夹";
+    #[cfg(feature = "en")]
+    pub const TIP_SCANNING: &'static str = "adding folder";
+    #[cfg(feature = "zh")]
+    pub const TIP_NO_MEDIA_FILES: &'static str = "没有可以播放的文件";
+    #[cfg(feature = "en")]
+    pub const TIP_NO_MEDIA_FILES: &'static str = "no playable files";
+    #[cfg(feature = "zh")]
+    pub const TIP_SAVED_TO_PLAYLIST: &'static str = "已保存到";
+    #[cfg(feature = "en")]
+    pub const TIP_SAVED_TO_PLAYLIST: &'static str = "saved to";
+    #[cfg(feature = "zh")]
+    pub const TIP_PLAYLIST_SAVE_FAILED: &'static str = "无法保存播放列表";
+    #[cfg(feature = "en")]
+    pub const TIP_PLAYLIST_SAVE_FAILED: &'static str = "cannot save playlist";
+    #[cfg(feature = "zh")]
+    pub const TIP_PLAYLIST_OPERATION_FAILED: &'static str = "无法修改播放列表";
+    #[cfg(feature = "en")]
+    pub const TIP_PLAYLIST_OPERATION_FAILED: &'static str = "cannot change playlist";
+    #[cfg(feature = "zh")]
+    pub const TIP_PLAYLIST_IS_ACTIVE: &'static str = "不能删除正在使用的播放列表";
+    #[cfg(feature = "en")]
+    pub const TIP_PLAYLIST_IS_ACTIVE: &'static str = "cannot delete the active playlist";
+    #[cfg(feature = "zh")]
+    pub const TIP_QUERY_INVALID: &'static str = "无效的查询";
+    #[cfg(feature = "en")]
+    pub const TIP_QUERY_INVALID: &'static str = "invalid query";
+    #[cfg(feature = "zh")]
+    pub const TIP_PLAYLIST_NAME_INVALID: &'static str = "名称无效";
+    #[cfg(feature = "en")]
+    pub const TIP_PLAYLIST_NAME_INVALID: &'static str = "invalid name";
+    #[cfg(feature = "zh")]
+    pub const TIP_PLAYLIST_EXISTS: &'static str = "已经存在";
+    #[cfg(feature = "en")]
+    pub const TIP_PLAYLIST_EXISTS: &'static str = "already exists";
+    #[cfg(feature = "zh")]
+    pub const TIP_STATS_SAVE_FAILED: &'static str = "无法保存播放统计";
+    #[cfg(feature = "en")]
+    pub const TIP_STATS_SAVE_FAILED: &'static str = "cannot save play stats";
+    #[cfg(feature = "zh")]
+    pub const STATS_NAMES: [&'static str; 5] = ["播放次数", "跳过次数", "评分", "收藏", "最后播放"];
+    #[cfg(feature = "en")]
+    pub const STATS_NAMES: [&'static str; 5] =
+        ["Plays", "Skips", "Rating", "Favorite", "Last played"];
+    #[cfg(feature = "zh")]
+    pub const TIP_FILE_MISSING: &'static str = "文件不存在";
+    #[cfg(feature = "en")]
+    pub const TIP_FILE_MISSING: &'static str = "missing";
+    #[cfg(feature = "zh")]
+    pub const TAG_FIELD_NAMES: [&'static str; 7] =
+        ["标题", "表演者", "专辑", "曲目", "流派", "年份", "歌词"];
+    #[cfg(feature = "en")]
+    pub const TAG_FIELD_NAMES: [&'static str; 7] = [
+        "Title", "Artist", "Album", "Track", "Genre", "Year", "Lyrics",
+    ];
+    #[cfg(feature = "zh")]
+    pub const TIP_TAG_SAVE_FAILED: &'static str = "无法保存标签";
+    #[cfg(feature = "en")]
+    pub const TIP_TAG_SAVE_FAILED: &'static str = "cannot save tags";
+    #[cfg(feature = "zh")]
+    pub const TIP_NO_SYNCED_LYRICS: &'static str = "没有同步歌词";
+    #[cfg(feature = "en")]
+    pub const TIP_NO_SYNCED_LYRICS: &'static str = "no synced lyrics";
+    #[cfg(feature = "zh")]
+    pub const TIP_NO_LYRICS: &'static str = "暂无歌词";
+    #[cfg(feature = "en")]
+    pub const TIP_NO_LYRICS: &'static str = "no lyrics";
 
     // tui block title
     pub const TITLE_SOFTWARE: &'static str = "RustPlayer - Player For Rust";
@@ -38,10 +150,49 @@ impl Config {
     #[cfg(feature = "en")]
     pub const TITLE_MEIDA_INFO: &'static str = "info";
     #[cfg(feature = "zh")]
+    pub const TITLE_LYRICS_SYNC: &'static str = "歌词同步";
+    #[cfg(feature = "en")]
+    pub const TITLE_LYRICS_SYNC: &'static str = "Lyrics Sync";
+    #[cfg(feature = "zh")]
+    pub const TITLE_TAG_EDITOR: &'static str = "编辑标签";
+    #[cfg(feature = "en")]
+    pub const TITLE_TAG_EDITOR: &'static str = "Edit Tags";
+    #[cfg(feature = "zh")]
+    pub const TITLE_COVER: &'static str = "封面";
+    #[cfg(feature = "en")]
+    pub const TITLE_COVER: &'static str = "Cover";
+    #[cfg(feature = "zh")]
     pub const TITLE_PLAY_LIST: &'static str = "播放列表";
     #[cfg(feature = "en")]
     pub const TITLE_PLAY_LIST: &'static str = "Play List";
     #[cfg(feature = "zh")]
+    pub const TITLE_UP_NEXT: &'static str = "下一首播放";
+    #[cfg(feature = "en")]
+    pub const TITLE_UP_NEXT: &'static str = "Up Next";
+    #[cfg(feature = "zh")]
+    pub const TITLE_PLAYLISTS: &'static str = "播放列表管理";
+    #[cfg(feature = "en")]
+    pub const TITLE_PLAYLISTS: &'static str = "Playlists";
+    #[cfg(feature = "zh")]
+    pub const TITLE_PLAYLIST_PICKER: &'static str = "添加到播放列表";
+    #[cfg(feature = "en")]
+    pub const TITLE_PLAYLIST_PICKER: &'static str = "Add to Playlist";
+    #[cfg(feature = "zh")]
+    pub const PLAYLIST_INPUT_NAMES: [&'static str; 5] =
+        ["新建", "重命名", "复制为", "新建智能播放列表", "查询"];
+    #[cfg(feature = "en")]
+    pub const PLAYLIST_INPUT_NAMES: [&'static str; 5] = [
+        "new",
+        "rename",
+        "duplicate as",
+        "new smart playlist",
+        "query",
+    ];
+    #[cfg(feature = "zh")]
+    pub const TITLE_SMART_PLAYLIST: &'static str = "智能";
+    #[cfg(feature = "en")]
+    pub const TITLE_SMART_PLAYLIST: &'static str = "smart";
+    #[cfg(feature = "zh")]
     pub const TITLE_EXPLORER: &'static str = "文件夹";
     #[cfg(feature = "en")]
     pub const TITLE_EXPLORER: &'static str = "Explorer";
@@ -62,46 +213,112 @@ impl Config {
     #[cfg(feature = "en")]
     pub const TITLE_WAVE: &'static str = "Wave";
 
+    // 文件夹排序依据 ; 与 SortMode 顺序一致
+    #[cfg(feature = "zh")]
+    pub const SORT_MODE_NAMES: [&'static str; 8] = [
+        "名称",
+        "自然顺序",
+        "修改时间",
+        "大小",
+        "时长",
+        "曲目",
+        "播放次数",
+        "评分",
+    ];
+    #[cfg(feature = "en")]
+    pub const SORT_MODE_NAMES: [&'static str; 8] = [
+        "name", "natural", "modified", "size", "duration", "track", "plays", "rating",
+    ];
+
     // shortcut key
     #[cfg(feature = "zh")]
-    pub const SHORTCUT_KEY_PLAY_LIST: &'static str = "Enter(立即播放) T(置顶) Delete|Backspace(删除)";
+    pub const SHORTCUT_KEY_PLAY_LIST: &'static str =
+        "Enter(立即播放) P(下一首播放) X(清空下一首播放) M(标记) V(范围选择) T(移到正在播放之后) [数字]Shift+⬆⬇(移动) B(移到末尾) 鼠标拖动(排序) Ctrl+Z|Ctrl+Y(撤销|重做) C(只保留选中) W(保存到播放列表) Delete|Backspace(删除) /(搜索) n|N(下一个|上一个匹配)";
+    #[cfg(feature = "en")]
+    pub const SHORTCUT_KEY_PLAY_LIST: &'static str =
+        "Enter(play) P(play next) X(clear up next) M(mark) V(range) T(move after current) [count]Shift+⬆⬇(move) B(move to end) mouse drag(reorder) Ctrl+Z|Ctrl+Y(undo|redo) C(crop) W(save to playlist) Delete|Backspace(delete) /(search) n|N(next|prev match)";
+    #[cfg(feature = "zh")]
+    pub const SHORTCUT_KEY_EXPLORER: &'static str =
+        "Enter(添加) M(标记) V(范围选择) W(保存到播放列表) A(追加) P(下一首播放) Ctrl+R(替换播放列表) Backspace(返回上一级) S(排序) R(倒序) H(隐藏文件夹) /(搜索) n|N(下一个|上一个匹配)";
+    #[cfg(feature = "en")]
+    pub const SHORTCUT_KEY_EXPLORER: &'static str =
+        "Enter(add) M(mark) V(range) W(save to playlist) A(append) P(play next) Ctrl+R(replace queue) Backspace(back) S(sort) R(reverse) H(hidden dirs) /(search) n|N(next|prev match)";
+    #[cfg(feature = "zh")]
+    pub const SHORTCUT_KEY_PLAYLISTS: &'static str =
+        "Enter(切换到该播放列表|加载智能播放列表) A(新建) S(新建智能播放列表) U(编辑查询) R(重命名) D(复制) Delete(删除) 查询示例: genre = Jazz AND year < 1970 LIMIT 50 RANDOM";
     #[cfg(feature = "en")]
-    pub const SHORTCUT_KEY_PLAY_LIST: &'static str = "Enter(play) T(top-post) Delete|Backspace(delete)";
+    pub const SHORTCUT_KEY_PLAYLISTS: &'static str =
+        "Enter(switch to playlist|load smart playlist) A(new) S(new smart playlist) U(edit query) R(rename) D(duplicate) Delete(delete) query e.g. genre = Jazz AND year < 1970 LIMIT 50 RANDOM";
     #[cfg(feature = "zh")]
-    pub const SHORTCUT_KEY_EXPLORER: &'static str = "Enter(添加) Backspace(返回上一级)";
+    pub const SHORTCUT_KEY_PICKER: &'static str = "⬆⬇(选择播放列表) Enter(添加) Esc(取消)";
     #[cfg(feature = "en")]
-    pub const SHORTCUT_KEY_EXPLORER: &'static str = "Enter(add) Backspace(back)";
+    pub const SHORTCUT_KEY_PICKER: &'static str = "⬆⬇(select playlist) Enter(add) Esc(cancel)";
+    #[cfg(feature = "zh")]
+    pub const SHORTCUT_KEY_TAG_EDITOR: &'static str =
+        "⬆⬇(选择字段) Enter(编辑|确认) S(保存) Esc(取消|退出编辑)";
+    #[cfg(feature = "en")]
+    pub const SHORTCUT_KEY_TAG_EDITOR: &'static str =
+        "⬆⬇(select field) Enter(edit|confirm) S(save) Esc(cancel|quit editor)";
+    #[cfg(feature = "zh")]
+    pub const SHORTCUT_KEY_LYRICS: &'static str =
+        "⬆⬇(选择歌词) Enter(跳转) /(搜索) n|N(下一个|上一个匹配) G|Esc(退出歌词)";
+    #[cfg(feature = "en")]
+    pub const SHORTCUT_KEY_LYRICS: &'static str =
+        "⬆⬇(select line) Enter(seek) /(search) n|N(next|prev match) G|Esc(leave lyrics)";
+    #[cfg(feature = "zh")]
+    pub const SHORTCUT_KEY_LYRIC_SYNC: &'static str =
+        "Enter(标记当前行) ⬆⬇(选择行) [](微调) S(保存) Esc(退出同步) Space(暂停|播放) ⬅➡(前进后退)";
+    #[cfg(feature = "en")]
+    pub const SHORTCUT_KEY_LYRIC_SYNC: &'static str =
+        "Enter(stamp line) ⬆⬇(select line) [](nudge) S(save) Esc(quit sync) Space(pause|play) ⬅➡(forward|reverse)";
     #[cfg(feature = "zh")]
     pub const SHORTCUT_KEY_COMMON: &'static str =
-        "Tab(切换) Space(暂停|播放) I(详情) N(下一首) ⬅➡(前进后退) -+(调整音量) F(全屏) Q(退出)";
+        "Tab(切换) Space(暂停|播放) I(详情) E(编辑标签) L(歌词翻译) [](歌词偏移) O(保存偏移) Y(同步歌词) G(选择歌词) N(下一首) Alt+1-5(评分) *(收藏) ⬅➡(前进后退) -+(调整音量) F(全屏) K(歌词界面) Q(退出)";
     #[cfg(feature = "en")]
     pub const SHORTCUT_KEY_COMMON: &'static str =
-        "Tab(change) Space(pause|play) I(info) N(next) ⬅➡(forward|reverse) -+(volume) F(full screen) Q(quit)";
+        "Tab(change) Space(pause|play) I(info) E(edit tags) L(translation) [](lyric offset) O(save offset) Y(sync lyrics) G(lyrics) N(next) Alt+1-5(rate) *(favorite) ⬅➡(forward|reverse) -+(volume) F(full screen) K(karaoke) Q(quit)";
 
-    // 可以解析的媒体文件
+    // 可以解析的媒体文件 ; 由可用的解码器决定
     pub fn is_accepted_file(f: &DirEntry) -> bool {
         let path = f.path();
-        if path.is_file() {
-            return if let Some(extension) = f.path().extension().and_then(OsStr::to_str) {
-                Config::ACCEPT_SUFFIX
-                    .iter()
-                    .any(|&suffix| suffix == extension)
-            } else {
-                false
-            };
-        }
-        false
+        path.is_file() && decoder::is_supported(&path)
+    }
+
+    // 数据文件夹 ; $XDG_DATA_HOME/rust-player，默认为 ~/.local/share/rust-player
+    pub fn data_dir() -> PathBuf {
+        std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|it| PathBuf::from(it).join(".local/share")))
+            .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+            .unwrap_or_else(std::env::temp_dir)
+            .join("rust-player")
     }
 
-    // 可以解析的目录 ; 不显示隐藏目录
-    pub fn is_accepted_dir(d: &DirEntry) -> bool {
+    // 可以解析的目录 ; 默认不显示隐藏目录
+    pub fn is_accepted_dir(d: &DirEntry, show_hidden: bool) -> bool {
         if d.path().is_dir() {
             let file_name = d.file_name().to_string_lossy().to_string();
-            if file_name.starts_with('.') {
+            if file_name.starts_with('.') && !show_hidden {
                 return false;
             }
             return true;
         }
         false
     }
+
+    // 专辑封面文件 ; cover.jpg folder.png 等，不区分大小写
+    pub fn is_cover_file(path: &Path) -> bool {
+        let stem = path.file_stem().and_then(OsStr::to_str);
+        let extension = path.extension().and_then(OsStr::to_str);
+        match (stem, extension) {
+            (Some(stem), Some(extension)) => {
+                let stem = stem.to_lowercase();
+                let extension = extension.to_lowercase();
+                path.is_file()
+                    && Config::COVER_NAMES.contains(&stem.as_str())
+                    && Config::COVER_SUFFIX.contains(&extension.as_str())
+            }
+            _ => false,
+        }
+    }
 }
@@ -0,0 +1,62 @@
+use std::{
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::Duration,
+};
+
+use crossterm::event::{self, Event};
+
+use crate::{media::Media, script::EnqueueMode};
+
+// 应用事件 ; 所有事件通过同一个channel发送给界面
+pub enum AppEvent {
+    Input(Event),                       // 键盘、鼠标、窗口大小变化
+    Tick,                               // 定时刷新 ; 播放进度、歌词滚动
+    TrackEnd(usize),                    // 歌曲播放完毕 ; 播放序号
+    Loaded(Box<Media>),                 // 后台解析完成的媒体
+    FileChanged,                        // 监听的目录中有文件增加、删除或重命名
+    ScanProgress(usize, usize),         // 添加文件夹时读取标签的进度
+    Scanned(EnqueueMode, Vec<PathBuf>), // 添加文件夹时找到的媒体文件
+}
+
+pub struct Events {
+    sender: Sender<AppEvent>,
+    receiver: Receiver<AppEvent>,
+}
+
+impl Events {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self { sender, receiver }
+    }
+
+    // 事件发送端 ; 交给播放器、后台任务使用
+    pub fn sender(&self) -> Sender<AppEvent> {
+        self.sender.clone()
+    }
+
+    // 在后台线程读取终端输入
+    pub fn listen_input(&self) {
+        let sender = self.sender();
+        thread::spawn(move || {
+            while let Ok(event) = event::read() {
+                if sender.send(AppEvent::Input(event)).is_err() {
+                    return;
+                }
+            }
+        });
+    }
+
+    // 等待下一个事件 ; 超时返回Tick，没有超时时间时一直等待
+    pub fn next(&self, tick_rate: Option<Duration>) -> AppEvent {
+        let result = match tick_rate {
+            Some(tick_rate) => self.receiver.recv_timeout(tick_rate),
+            None => self
+                .receiver
+                .recv()
+                .map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        result.unwrap_or(AppEvent::Tick)
+    }
+}
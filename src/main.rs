@@ -1,7 +1,9 @@
 use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use events::Events;
 
+use media::subtitle::{self, SubtitleFormat};
 use script::Script;
 use ui::UI;
 
@@ -9,8 +11,11 @@ use crate::config::Config;
 
 /// a tui application for audio player
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, args_conflicts_with_subcommands = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// open dir
     path: Option<PathBuf>,
 
@@ -26,7 +31,25 @@ struct Args {
     info: bool,
 }
 
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// export lyrics as subtitles
+    Export {
+        /// media file, or a directory to convert all media files in it
+        path: PathBuf,
+
+        /// subtitle format
+        #[arg(short, long, value_enum, default_value_t = SubtitleFormat::Srt)]
+        format: SubtitleFormat,
+
+        /// output directory, defaults to the directory of each media file
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
 mod config;
+mod events;
 mod media;
 mod script;
 mod ui;
@@ -36,6 +59,15 @@ fn main() -> Result<(), std::io::Error> {
     let args: Args = Args::parse();
     let current_dir = std::env::current_dir().unwrap();
 
+    if let Some(Command::Export {
+        path,
+        format,
+        output,
+    }) = args.command
+    {
+        return subtitle::export(&path, format, output.as_deref());
+    }
+
     let path = match args.path {
         Some(path) => {
             if path.is_absolute() {
@@ -54,7 +86,8 @@ fn main() -> Result<(), std::io::Error> {
 
     let full_screen = args.full;
     let show_info = args.info;
-    let mut script = Script::new()?;
+    let events = Events::new();
+    let mut script = Script::new(events.sender())?;
 
     if path.is_file() {
         script.add_local_file_to_play_list(&path);
@@ -72,7 +105,7 @@ fn main() -> Result<(), std::io::Error> {
         }
     }
 
-    UI::new(script, full_screen, show_info)?;
+    UI::new(script, events, full_screen, show_info)?;
 
     Ok(())
 }
@@ -0,0 +1,92 @@
+use std::{
+    ffi::OsStr,
+    fs::File,
+    io::{self, BufReader},
+    path::Path,
+};
+
+use rodio::{Decoder, Source};
+
+#[cfg(feature = "opus")]
+use super::opus::OpusDecoder;
+
+// 解码后的音频源
+pub type AudioSource = Box<dyn Source<Item = i16> + Send>;
+
+// 可以解码的文件后缀 ; 可选的解码器按 feature 加入，没有启用时不展示对应的文件
+const SUPPORTED_SUFFIX: &[&str] = &[
+    // rodio 默认解码器
+    "mp3",
+    "wav",
+    "flac",
+    "ogg",
+    "oga",
+    "ts",
+    // symphonia ; aac、mp4、aiff
+    "aac",
+    "m4a",
+    "m4b",
+    "mp4",
+    "aif",
+    "aiff",
+    // libopus
+    #[cfg(feature = "opus")]
+    "opus",
+];
+
+// 是否可以解码 ; 后缀不区分大小写
+pub fn is_supported(path: &Path) -> bool {
+    match path.extension().and_then(OsStr::to_str) {
+        Some(extension) => SUPPORTED_SUFFIX.contains(&extension.to_lowercase().as_str()),
+        None => false,
+    }
+}
+
+// 打开音频文件 ; 不支持的格式或损坏的文件返回错误
+pub fn open(path: &Path) -> io::Result<AudioSource> {
+    if !is_supported(path) {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "unsupported format",
+        ));
+    }
+    let reader = BufReader::new(File::open(path)?);
+
+    #[cfg(feature = "opus")]
+    if path
+        .extension()
+        .and_then(OsStr::to_str)
+        .is_some_and(|it| it.eq_ignore_ascii_case("opus"))
+    {
+        return Ok(Box::new(OpusDecoder::new(reader)?));
+    }
+
+    let decoder =
+        Decoder::new(reader).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok(Box::new(decoder))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suffix_is_case_insensitive() {
+        assert!(is_supported(Path::new("a.mp3")));
+        assert!(is_supported(Path::new("a.FLAC")));
+        assert!(is_supported(Path::new("dir/a.M4a")));
+        assert!(!is_supported(Path::new("a.txt")));
+        assert!(!is_supported(Path::new("mp3")));
+    }
+
+    // 没有启用 opus 时不展示 opus 文件，避免播放时才报错
+    #[test]
+    fn opus_follows_feature() {
+        assert_eq!(is_supported(Path::new("a.opus")), cfg!(feature = "opus"));
+        let unsupported = match open(Path::new("missing.opus")) {
+            Err(e) => e.kind() == io::ErrorKind::Unsupported,
+            Ok(_) => false,
+        };
+        assert_eq!(unsupported, !cfg!(feature = "opus"));
+    }
+}
@@ -0,0 +1,58 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        mpsc::{self, Sender},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use crate::events::AppEvent;
+
+use super::Media;
+
+// 后台解析媒体文件的标签、歌词和时长 ; 解析完成后发送 AppEvent::Loaded
+pub struct Loader {
+    jobs: Sender<PathBuf>, // 待解析的文件
+}
+
+impl Loader {
+    // 最多使用的线程数
+    const MAX_WORKERS: usize = 4;
+
+    pub fn new(events: Sender<AppEvent>) -> Self {
+        let (jobs, job_receiver) = mpsc::channel::<PathBuf>();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+
+        let workers = thread::available_parallelism()
+            .map(|it| it.get())
+            .unwrap_or(1)
+            .min(Self::MAX_WORKERS);
+        for _ in 0..workers {
+            let job_receiver = job_receiver.clone();
+            let events = events.clone();
+            thread::spawn(move || loop {
+                let job = match job_receiver.lock() {
+                    Ok(receiver) => receiver.recv(),
+                    Err(_) => return,
+                };
+                // 发送端已经关闭
+                let Ok(path) = job else {
+                    return;
+                };
+                if let Some(media) = Media::new_local_file(path) {
+                    if events.send(AppEvent::Loaded(Box::new(media))).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+
+        Self { jobs }
+    }
+
+    // 添加解析任务
+    pub fn load(&self, path: PathBuf) {
+        let _ = self.jobs.send(path);
+    }
+}
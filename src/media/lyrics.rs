@@ -1,29 +1,66 @@
-use std::{cmp, fs::File, io::Read, path::PathBuf, time::Duration};
+use std::{
+    cmp,
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use regex::Regex;
+use regex::{Captures, Regex};
+
+use crate::utils;
 
 #[derive(Clone)]
 pub struct Lyric {
-    pub time: Duration,  // 播放时刻
-    pub content: String, // 歌词内容
+    pub time: Duration,              // 播放时刻
+    pub content: String,             // 歌词内容 ; 不包含逐字时间
+    pub words: Vec<LyricWord>,       // 逐字时间 ; 普通lrc为空
+    pub translation: Option<String>, // 翻译
 }
 
+// 增强型lrc中的单词 ; <mm:ss.xx>word
 #[derive(Clone)]
+pub struct LyricWord {
+    pub time: Duration,  // 开始唱的时刻
+    pub content: String, // 单词内容
+}
+
+#[derive(Clone, Default)]
 pub struct Lyrics {
-    pub list: Vec<Lyric>,
+    pub list: Vec<Lyric>,         // 按时间排序的歌词
+    pub unsynced: Vec<String>,    // 没有时间标签的歌词 ; 只在list为空时有值
+    pub title: Option<String>,    // [ti:] 标题
+    pub artist: Option<String>,   // [ar:] 表演者
+    pub album: Option<String>,    // [al:] 专辑
+    pub by: Option<String>,       // [by:] 歌词作者
+    pub length: Option<Duration>, // [length:] 歌曲时长
+    pub offset: i64,              // [offset:] 毫秒 ; 正数表示歌词提前显示
+    pub adjust: i64,              // 播放时调整的偏移 ; 毫秒，未保存到文件
+    pub source: Option<PathBuf>,  // 歌词文件 ; 内嵌歌词为None
 }
 
 impl Lyrics {
-    const LRC_REGEX: &'static str = r"\[(?P<min>\d+):(?P<sec>\d+).(?P<ms>\d+)](?P<content>[^\[\]]*)";
+    // 行首的标签 ; 时间标签 [mm:ss.xx] 或信息标签 [key:value]
+    const TAG_REGEX: &'static str = r"^\[(?P<key>[^\[\]:]*):(?P<value>[^\[\]]*)]";
+    const TIME_REGEX: &'static str = r"^(?P<min>\d+):(?P<sec>\d+)(?:[.:](?P<ms>\d+))?$";
+    const WORD_REGEX: &'static str = r"<(?P<min>\d+):(?P<sec>\d+)(?:[.:](?P<ms>\d+))?>";
+    const OFFSET_REGEX: &'static str = r"(?im)^\[offset:[^\[\]]*]\r?\n?";
 
     // 读取歌词文件
     pub fn from_music_path(s: &PathBuf) -> Self {
         let mut p = PathBuf::from(s);
         p.set_extension("lrc");
-        if let Ok(ref mut file) = File::open(p) {
+        if let Ok(ref mut file) = File::open(&p) {
+            let mut lyrics = Self::from_read(file);
+            lyrics.source = Some(p);
+            return lyrics;
+        }
+        // 纯文本歌词
+        p.set_extension("txt");
+        if let Ok(ref mut file) = File::open(&p) {
             Self::from_read(file)
         } else {
-            Self { list: vec![] }
+            Self::default()
         }
     }
 
@@ -31,31 +68,463 @@ impl Lyrics {
     pub fn from_read(f: &mut File) -> Self {
         let mut buffer = vec![];
         let _ = f.read_to_end(&mut buffer);
-        let m = String::from_utf8(buffer).unwrap_or(String::default());
 
-        Self::from_string(m)
+        Self::from_string(utils::decode_text(&buffer))
     }
 
     pub fn from_string(strs: String) -> Self {
-        let regex = Regex::new(Lyrics::LRC_REGEX).unwrap();
-
-        let list = regex
-            .captures_iter(strs.as_str())
-            .map(|cap| {
-                let min = cap["min"].parse::<u64>().unwrap_or(0);
-                let sec = cap["sec"].parse::<u64>().unwrap_or(0);
-                let sec = cmp::min(cmp::max(sec, 0), 59); // 0s-59s
-                let ms = cap["ms"].parse::<u64>().unwrap_or(0);
-                let ms = cmp::min(cmp::max(ms, 0), 999); // 0ms-999ms
-                let dur = Duration::from_millis(ms + sec * 1000 + min * 1000 * 60);
-
-                Lyric {
-                    time: dur,
-                    content: String::from(&cap["content"]),
+        let tag_regex = Regex::new(Lyrics::TAG_REGEX).unwrap();
+        let time_regex = Regex::new(Lyrics::TIME_REGEX).unwrap();
+        let word_regex = Regex::new(Lyrics::WORD_REGEX).unwrap();
+
+        let mut lyrics = Self::default();
+        for line in strs.lines() {
+            // 一行可以有多个时间标签 ; [00:12.00][01:30.00]chorus
+            let mut rest = line.trim_start_matches('\u{feff}').trim();
+            let mut times = vec![];
+            while let Some(cap) = tag_regex.captures(rest) {
+                let key = cap["key"].trim().to_lowercase();
+                let value = cap["value"].trim();
+                match time_regex.captures(&format!("{}:{}", key, value)) {
+                    Some(time) => times.push(Self::parse_time(&time)),
+                    None => lyrics.set_tag(&key, value),
                 }
+                rest = &rest[cap.get(0).unwrap().end()..];
+            }
+
+            if times.is_empty() && !rest.trim().is_empty() {
+                lyrics.unsynced.push(rest.trim().to_string());
+            }
+            for time in times {
+                let (content, words) = Self::parse_words(&word_regex, time, rest.trim());
+                lyrics.list.push(Lyric {
+                    time,
+                    content,
+                    words,
+                    translation: None,
+                });
+            }
+        }
+
+        // 偏移对整首歌生效，与标签所在位置无关
+        let offset = lyrics.offset;
+        for it in lyrics.list.iter_mut() {
+            it.time = Self::apply_offset(it.time, offset);
+            for word in it.words.iter_mut() {
+                word.time = Self::apply_offset(word.time, offset);
+            }
+        }
+        // 稳定排序 ; 相同时间保持文件中的顺序
+        lyrics.list.sort_by_key(|it| it.time);
+        lyrics.pair_translations();
+        if !lyrics.list.is_empty() {
+            lyrics.unsynced.clear();
+        }
+        lyrics
+    }
+
+    // 格式化为lrc时间标签 ; mm:ss.xx
+    pub fn format_time(time: Duration) -> String {
+        let centis = time.as_millis() / 10;
+        format!(
+            "[{:02}:{:02}.{:02}]",
+            centis / 6000,
+            centis / 100 % 60,
+            centis % 100
+        )
+    }
+
+    // 时间相同的两行，后一行作为前一行的翻译
+    fn pair_translations(&mut self) {
+        let mut list: Vec<Lyric> = Vec::with_capacity(self.list.len());
+        for it in self.list.drain(..) {
+            if let Some(last) = list.last_mut() {
+                if last.time == it.time
+                    && last.translation.is_none()
+                    && !last.content.is_empty()
+                    && !it.content.is_empty()
+                {
+                    last.translation = Some(it.content);
+                    continue;
+                }
+            }
+            list.push(it);
+        }
+        self.list = list;
+    }
+
+    // 读取同目录下的翻译文件 ; song.zh.lrc、song.en.lrc 等
+    pub fn with_translation(mut self, music: &Path) -> Self {
+        if self.list.is_empty() || self.has_translation() {
+            return self;
+        }
+        let (Some(dir), Some(stem)) = (music.parent(), music.file_stem()) else {
+            return self;
+        };
+        let prefix = format!("{}.", stem.to_string_lossy());
+        let Ok(entries) = fs::read_dir(dir) else {
+            return self;
+        };
+        let mut files: Vec<PathBuf> = entries
+            .flatten()
+            .map(|it| it.path())
+            .filter(|it| {
+                let name = it.file_name().unwrap_or_default().to_string_lossy();
+                // 排除 song.lrc 本身
+                name.starts_with(&prefix)
+                    && name.to_lowercase().ends_with(".lrc")
+                    && name.len() > prefix.len() + "lrc".len()
             })
             .collect();
+        files.sort();
+
+        if let Some(Ok(ref mut file)) = files.first().map(File::open) {
+            let translation = Self::from_read(file);
+            for it in self.list.iter_mut() {
+                it.translation = translation
+                    .list
+                    .iter()
+                    .find(|line| line.time == it.time && !line.content.is_empty())
+                    .map(|line| line.content.clone());
+            }
+        }
+        self
+    }
+
+    // 是否有翻译
+    pub fn has_translation(&self) -> bool {
+        self.list.iter().any(|it| it.translation.is_some())
+    }
+
+    // 调整偏移
+    pub fn shift(&mut self, delta: i64) {
+        self.adjust += delta;
+    }
+
+    // 调整偏移后，歌词对应的播放位置
+    pub fn position(&self, position: Duration) -> Duration {
+        Self::apply_offset(position, -self.adjust)
+    }
+
+    // 歌词时刻对应的播放位置 ; position的逆运算
+    pub fn playback_position(&self, time: Duration) -> Duration {
+        Self::apply_offset(time, self.adjust)
+    }
+
+    // 把调整后的偏移写回歌词文件的 [offset:] 标签
+    pub fn save_offset(&self) -> io::Result<()> {
+        let Some(path) = &self.source else {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "lrc"));
+        };
+        // 统一保存为utf-8
+        let text = utils::decode_text(&fs::read(path)?);
+        let text = text.trim_start_matches('\u{feff}');
+        let regex = Regex::new(Lyrics::OFFSET_REGEX).unwrap();
+        let text = format!(
+            "[offset:{:+}]\n{}",
+            self.offset + self.adjust,
+            regex.replace_all(text, "")
+        );
+        fs::write(path, text)
+    }
+
+    // 内嵌的同步歌词 ; (时刻, 文字)
+    // 文字以换行开头时为逐字歌词，否则每一段为一行
+    pub fn from_synced(segments: &[(Duration, String)]) -> Self {
+        let by_word = segments
+            .iter()
+            .any(|(_, text)| text.starts_with(['\n', '\r']));
+        let mut lyrics = Self::default();
+        for (time, text) in segments {
+            let new_line = !by_word || text.starts_with(['\n', '\r']) || lyrics.list.is_empty();
+            let text = text.trim_start_matches(['\n', '\r']);
+            if new_line {
+                lyrics.list.push(Lyric {
+                    time: *time,
+                    content: String::new(),
+                    words: vec![],
+                    translation: None,
+                });
+            }
+            if let Some(last) = lyrics.list.last_mut() {
+                last.content.push_str(text);
+                if by_word {
+                    last.words.push(LyricWord {
+                        time: *time,
+                        content: text.to_string(),
+                    });
+                }
+            }
+        }
+        lyrics.list.sort_by_key(|it| it.time);
+        lyrics.pair_translations();
+        lyrics
+    }
+
+    // 信息标签
+    fn set_tag(&mut self, key: &str, value: &str) {
+        let text = if value.is_empty() {
+            None
+        } else {
+            Some(value.to_string())
+        };
+        match key {
+            "ti" => self.title = text,
+            "ar" => self.artist = text,
+            "al" => self.album = text,
+            "by" => self.by = text,
+            "length" => self.length = Self::parse_length(value),
+            "offset" => self.offset = value.trim_start_matches('+').parse().unwrap_or(0),
+            _ => {}
+        }
+    }
+
+    // [length: 03:25] 或 [length: 03:25.50]
+    fn parse_length(value: &str) -> Option<Duration> {
+        let regex = Regex::new(Lyrics::TIME_REGEX).unwrap();
+        regex.captures(value).map(|cap| Self::parse_time(&cap))
+    }
+
+    // 正数提前，负数推迟
+    fn apply_offset(time: Duration, offset: i64) -> Duration {
+        let delta = Duration::from_millis(offset.unsigned_abs());
+        if offset >= 0 {
+            time.saturating_sub(delta)
+        } else {
+            time + delta
+        }
+    }
+
+    // 解析时间标签 ; 小数部分按位数换算，.5 .50 .500 都是500毫秒
+    fn parse_time(cap: &Captures) -> Duration {
+        let min = cap["min"].parse::<u64>().unwrap_or(0);
+        let sec = cap["sec"].parse::<u64>().unwrap_or(0);
+        let ms = cap.name("ms").map_or(0, |it| {
+            let digits = &it.as_str()[..cmp::min(it.as_str().len(), 3)];
+            let value = digits.parse::<u64>().unwrap_or(0);
+            value * 10u64.pow(3 - digits.len() as u32)
+        });
+        Duration::from_millis(ms + sec * 1000 + min * 1000 * 60)
+    }
+
+    // 解析逐字时间 ; 第一个时间标签前的文字从行的时刻开始唱，行尾的时间标签为最后一个单词的结束时刻
+    fn parse_words(regex: &Regex, line_time: Duration, text: &str) -> (String, Vec<LyricWord>) {
+        if !regex.is_match(text) {
+            return (String::from(text), vec![]);
+        }
+
+        let mut words = vec![];
+        let mut time = line_time;
+        let mut last = 0;
+        for cap in regex.captures_iter(text) {
+            let m = cap.get(0).unwrap();
+            let word = &text[last..m.start()];
+            if !word.is_empty() {
+                words.push(LyricWord {
+                    time,
+                    content: String::from(word),
+                });
+            }
+            time = Self::parse_time(&cap);
+            last = m.end();
+        }
+        // 最后一个标签后没有文字时，保留空单词作为结束时刻
+        words.push(LyricWord {
+            time,
+            content: String::from(&text[last..]),
+        });
+
+        let content = words.iter().map(|it| it.content.as_str()).collect();
+        (content, words)
+    }
+}
+
+impl Lyrics {
+    // 正在唱的一行 ; 还没有开始唱时为None
+    pub fn current_index(&self, position: Duration) -> Option<usize> {
+        self.list
+            .partition_point(|it| it.time <= position)
+            .checked_sub(1)
+    }
+}
+
+impl Lyric {
+    // 已经唱到的文字长度 ; 按字节计算
+    pub fn sung_len(&self, position: Duration) -> usize {
+        self.words
+            .iter()
+            .take_while(|it| it.time <= position)
+            .map(|it| it.content.len())
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    fn times(lyrics: &Lyrics) -> Vec<u64> {
+        lyrics
+            .list
+            .iter()
+            .map(|it| it.time.as_millis() as u64)
+            .collect()
+    }
+
+    fn contents(lyrics: &Lyrics) -> Vec<&str> {
+        lyrics.list.iter().map(|it| it.content.as_str()).collect()
+    }
+
+    #[test]
+    fn repeated_timestamps_on_one_line() {
+        let lyrics = Lyrics::from_string("[00:12.00][01:30.00] chorus\n[00:20.00]verse".into());
+        assert_eq!(times(&lyrics), [12_000, 20_000, 90_000]);
+        assert_eq!(contents(&lyrics), ["chorus", "verse", "chorus"]);
+    }
+
+    #[test]
+    fn offset_tag_moves_all_lines() {
+        let text = "[00:10.00]a\n[offset:+500]\n[00:20.00]b";
+        let lyrics = Lyrics::from_string(text.into());
+        assert_eq!(lyrics.offset, 500);
+        assert_eq!(times(&lyrics), [9_500, 19_500]);
+
+        let lyrics = Lyrics::from_string("[offset:-250]\n[00:10.00]a".into());
+        assert_eq!(lyrics.offset, -250);
+        assert_eq!(times(&lyrics), [10_250]);
+
+        // 提前超过开头时从0开始
+        let lyrics = Lyrics::from_string("[offset:2000]\n[00:01.00]a".into());
+        assert_eq!(times(&lyrics), [0]);
+
+        // 无效的偏移忽略
+        let lyrics = Lyrics::from_string("[offset:abc]\n[00:01.00]a".into());
+        assert_eq!(lyrics.offset, 0);
+    }
+
+    #[test]
+    fn id_tags() {
+        let text = "[ti: So What]\n[ar:Miles Davis]\n[al:Kind of Blue]\n[by:]\n[length: 09:22]\n[re:editor]\n[00:01.00]a";
+        let lyrics = Lyrics::from_string(text.into());
+        assert_eq!(lyrics.title.as_deref(), Some("So What"));
+        assert_eq!(lyrics.artist.as_deref(), Some("Miles Davis"));
+        assert_eq!(lyrics.album.as_deref(), Some("Kind of Blue"));
+        assert_eq!(lyrics.by, None);
+        assert_eq!(lyrics.length, Some(ms(562_000)));
+        // 信息标签不作为歌词
+        assert_eq!(contents(&lyrics), ["a"]);
+        assert!(lyrics.unsynced.is_empty());
+    }
+
+    #[test]
+    fn unsorted_input_is_sorted_stably() {
+        let text = "[00:30.00]c\n[00:10.00]a\n[00:20.00]b1\n[00:05.00]start\n[00:20.00]b2";
+        let lyrics = Lyrics::from_string(text.into());
+        assert_eq!(times(&lyrics), [5_000, 10_000, 20_000, 30_000]);
+        // 相同时间的第二行作为翻译
+        assert_eq!(contents(&lyrics), ["start", "a", "b1", "c"]);
+        assert_eq!(lyrics.list[2].translation.as_deref(), Some("b2"));
+    }
+
+    #[test]
+    fn fraction_digits() {
+        let text = "[00:01.5]x\n[00:02.05]xx\n[00:03.005]xxx\n[00:04]none\n[00:05:50]colon\n[01:00.1234]long";
+        let lyrics = Lyrics::from_string(text.into());
+        assert_eq!(times(&lyrics), [1_500, 2_050, 3_005, 4_000, 5_500, 60_123]);
+    }
+
+    #[test]
+    fn utf8_bom() {
+        let lyrics = Lyrics::from_string("\u{feff}[ti:t]\n[00:01.00]a".into());
+        assert_eq!(lyrics.title.as_deref(), Some("t"));
+        assert_eq!(contents(&lyrics), ["a"]);
+
+        // 从文件读取
+        let mut data = b"\xef\xbb\xbf".to_vec();
+        data.extend_from_slice("[00:01.00]歌词".as_bytes());
+        let path = utils::temp_file("bom.lrc", &data);
+        let lyrics = Lyrics::from_read(&mut File::open(&path).unwrap());
+        fs::remove_file(&path).unwrap();
+        assert_eq!(contents(&lyrics), ["歌词"]);
+    }
+
+    #[test]
+    fn crlf_line_endings() {
+        let text = "[ti:t]\r\n[00:01.00]a\r\n[00:02.00]<00:02.00>b <00:02.50>c\r\n";
+        let lyrics = Lyrics::from_string(text.into());
+        assert_eq!(lyrics.title.as_deref(), Some("t"));
+        assert_eq!(contents(&lyrics), ["a", "b c"]);
+        assert!(!lyrics.list[1].content.ends_with('\r'));
+    }
+
+    #[test]
+    fn translation_pairing() {
+        let text = "[00:01.00]hello\n[00:01.00]你好\n[00:01.00]third\n[00:02.00]\n[00:02.00]empty";
+        let lyrics = Lyrics::from_string(text.into());
+        assert_eq!(contents(&lyrics), ["hello", "third", "", "empty"]);
+        assert_eq!(lyrics.list[0].translation.as_deref(), Some("你好"));
+        // 已经有翻译的行和空行不配对
+        assert_eq!(lyrics.list[1].translation, None);
+        assert_eq!(lyrics.list[2].translation, None);
+        assert!(lyrics.has_translation());
+    }
+
+    #[test]
+    fn unsynced_text_only_without_time_tags() {
+        let lyrics = Lyrics::from_string("line 1\n\nline 2".into());
+        assert!(lyrics.list.is_empty());
+        assert_eq!(lyrics.unsynced, ["line 1", "line 2"]);
+
+        let lyrics = Lyrics::from_string("note\n[00:01.00]a".into());
+        assert!(lyrics.unsynced.is_empty());
+    }
+
+    #[test]
+    fn word_times() {
+        let lyrics = Lyrics::from_string("[00:01.00]a<00:01.50>b<00:02.00>".into());
+        let words: Vec<(u64, &str)> = lyrics.list[0]
+            .words
+            .iter()
+            .map(|it| (it.time.as_millis() as u64, it.content.as_str()))
+            .collect();
+        assert_eq!(words, [(1_000, "a"), (1_500, "b"), (2_000, "")]);
+        assert_eq!(lyrics.list[0].sung_len(ms(1_600)), 2);
+    }
+
+    #[test]
+    fn save_offset_replaces_tag() {
+        let path = utils::temp_file("offset.lrc", b"[ti:t]\r\n[offset:+100]\r\n[00:10.00]a\r\n");
+        let mut lyrics = Lyrics::from_read(&mut File::open(&path).unwrap());
+        lyrics.source = Some(path.clone());
+        lyrics.shift(-300);
+        lyrics.save_offset().unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        let saved = Lyrics::from_string(text.clone());
+        fs::remove_file(&path).unwrap();
+        assert!(text.starts_with("[offset:-200]\n"));
+        assert_eq!(text.matches("offset").count(), 1);
+        assert_eq!(saved.offset, -200);
+        assert_eq!(times(&saved), [10_200]);
+
+        // 内嵌歌词不能保存
+        assert!(Lyrics::default().save_offset().is_err());
+    }
+
+    #[test]
+    fn position_and_playback_position_are_inverse() {
+        let mut lyrics = Lyrics::default();
+        lyrics.shift(500);
+        assert_eq!(lyrics.position(ms(10_000)), ms(10_500));
+        assert_eq!(lyrics.playback_position(ms(10_500)), ms(10_000));
+    }
 
-        Self { list }
+    #[test]
+    fn format_time() {
+        assert_eq!(Lyrics::format_time(ms(83_456)), "[01:23.45]");
+        assert_eq!(Lyrics::format_time(ms(0)), "[00:00.00]");
     }
 }
@@ -1,9 +1,10 @@
 use std::{path::PathBuf, time::Duration};
 
-use id3::{Tag, TagLike};
 use rodio::Source;
 
-use super::lyrics::Lyrics;
+use crate::config::Config;
+
+use super::{decoder, lyrics::Lyrics, metadata::Metadata};
 
 #[derive(Clone)]
 pub enum Media {
@@ -12,39 +13,55 @@ pub enum Media {
         name: String,
         duration: Duration,
         lyrics: Lyrics,
-        tag: Option<Tag>,
+        metadata: Metadata,
     },
 }
 
 impl Media {
     pub fn new_local_file(path: PathBuf) -> Option<Self> {
         if let Some(file_name) = path.file_name() {
-            let tag = Tag::read_from_path(&path).ok();
-            let lyrics = if let Some(tag) = &tag {
-                let mut string = String::default();
-                for it in tag.lyrics() {
-                    string = string + &it.text;
-                }
-                if string.len() > 0 {
-                    Lyrics::from_string(string)
+            let metadata = Metadata::read_from_path(&path).unwrap_or_default();
+            // 优先使用内嵌的同步歌词，其次是歌词文件，最后是内嵌的纯文本歌词
+            let embedded = if metadata.synced_lyrics.is_empty() {
+                Lyrics::from_string(metadata.lyrics.concat())
+            } else {
+                Lyrics::from_synced(&metadata.synced_lyrics)
+            };
+            let lyrics = if embedded.list.is_empty() {
+                let file = Lyrics::from_music_path(&path);
+                if file.list.is_empty() && file.unsynced.is_empty() {
+                    embedded
                 } else {
-                    Lyrics::from_music_path(&path)
+                    file
                 }
             } else {
-                Lyrics::from_music_path(&path)
-            };
+                embedded
+            }
+            .with_translation(&path);
 
             Some(Media::LocalFile {
                 path: PathBuf::from(path.clone()),
                 name: file_name.to_string_lossy().to_string(),
-                duration: Self::duration(&path),
+                duration: Self::duration(&path, &metadata),
                 lyrics,
-                tag,
+                metadata,
             })
         } else {
             None
         }
     }
+
+    // 未解析标签和时长的本地文件 ; 由后台加载完成后替换
+    pub fn new_local_file_placeholder(path: PathBuf) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_string();
+        Some(Media::LocalFile {
+            path,
+            name,
+            duration: Duration::default(),
+            lyrics: Lyrics::default(),
+            metadata: Metadata::default(),
+        })
+    }
 }
 
 impl Media {
@@ -59,18 +76,68 @@ impl Media {
             Media::LocalFile { duration, .. } => *duration,
         }
     }
-    // 获取本地文件播放时长
-    fn duration(path: &PathBuf) -> Duration {
+    // 获取文件路径
+    pub fn get_path(&self) -> &PathBuf {
+        match self {
+            Media::LocalFile { path, .. } => path,
+        }
+    }
+    // 获取本地文件播放时长 ; 依次使用文件头中记录的时长、mp3帧、解码器，最后完整解码计算
+    pub fn duration(path: &PathBuf, metadata: &Metadata) -> Duration {
+        if let Some(dur) = metadata.duration {
+            return dur;
+        }
         if let Ok(dur) = mp3_duration::from_path(path) {
             return dur;
-        } else if let Ok(file) = std::fs::File::open(path) {
-            if let Ok(decoder) = rodio::Decoder::new(file) {
-                if let Some(dur) = decoder.total_duration() {
-                    return dur;
+        }
+        if let Ok(decoder) = decoder::open(path) {
+            if let Some(dur) = decoder.total_duration() {
+                return dur;
+            }
+            // 可变码率等无法直接获取时长的格式，统计所有采样
+            let channels = decoder.channels().max(1) as f64;
+            let sample_rate = decoder.sample_rate().max(1) as f64;
+            let samples = decoder.count() as f64;
+            return Duration::from_secs_f64(samples / channels / sample_rate);
+        }
+        Duration::default()
+    }
+    // 获取标签
+    pub fn get_metadata(&self) -> &Metadata {
+        match self {
+            Media::LocalFile { metadata, .. } => metadata,
+        }
+    }
+    // 封面缓存的key ; 同一专辑共用一张封面
+    pub fn get_cover_key(&self) -> String {
+        match self {
+            Media::LocalFile { path, metadata, .. } => {
+                let dir = path.parent().unwrap_or(path).to_string_lossy().to_string();
+                match &metadata.album {
+                    Some(album) => format!("{}#{}", dir, album),
+                    None => dir,
+                }
+            }
+        }
+    }
+    // 获取封面图片数据 ; 优先使用内嵌的APIC封面，其次是同目录下的cover.jpg、folder.png等
+    pub fn get_cover(&self) -> Option<Vec<u8>> {
+        match self {
+            Media::LocalFile { path, metadata, .. } => {
+                if let Some(picture) = metadata.cover() {
+                    return Some(picture.data.clone());
                 }
+                let dir = path.parent()?;
+                let entries = std::fs::read_dir(dir).ok()?;
+                let mut images: Vec<PathBuf> = entries
+                    .flatten()
+                    .map(|it| it.path())
+                    .filter(|it| Config::is_cover_file(it))
+                    .collect();
+                images.sort();
+                images.first().and_then(|it| std::fs::read(it).ok())
             }
         }
-        return Duration::default();
     }
     // 获取歌词
     pub fn get_lyrics(&self) -> &Lyrics {
@@ -78,71 +145,101 @@ impl Media {
             Media::LocalFile { lyrics, .. } => lyrics,
         }
     }
-    // 获取id3 tag
-    pub fn get_id3_tag(&self) -> Vec<(String, String, String)> {
+    pub fn get_lyrics_mut(&mut self) -> &mut Lyrics {
         match self {
-            Media::LocalFile { tag, .. } => {
-                let mut items = vec![];
-
-                if let Some(tag) = tag {
-                    // TIT2 ; 歌曲标题名字
-                    if let Some(title) = tag.title() {
-                        items.push(("TIT2".to_string(), "标题".to_string(), title.to_string()))
-                    }
-                    // TPE1 ; 主要表演者/独奏者
-                    if let Some(artist) = tag.artist() {
-                        items.push(("TPE1".to_string(), "表演者".to_string(), artist.to_string()))
-                    }
-                    // TALB ; 专辑/电影/节目名称
-                    if let Some(album) = tag.album() {
-                        items.push(("TALB".to_string(), "专辑".to_string(), album.to_string()))
-                    }
-                    // TPE2 ; 乐队/管弦乐团/伴奏
-                    if let Some(album_artist) = tag.album_artist() {
-                        items.push((
-                            "TPE2".to_string(),
-                            "专辑集艺术家".to_string(),
-                            album_artist.to_string(),
-                        ))
-                    }
-                    // TCOM ; 作曲家
-                    if let Some(tcom) = tag.get("TCOM").and_then(|frame| frame.content().text()) {
-                        items.push(("TCOM".to_string(), "作曲家".to_string(), tcom.to_string()))
-                    }
-                    // TPOS ; 集数
-                    if let Some(d) = tag.disc() {
-                        items.push(("TPOS".to_string(), "集数".to_string(), d.to_string()))
-                    }
-                    // TCON ; 内容类型, 如流派、风格等
-                    if let Some(genre) = tag.genre() {
-                        items.push(("TCON".to_string(), "流派".to_string(), genre.to_string()))
-                    }
-                    // // TRCK ; 曲目编号/集合中位置
-                    // if let Some(track) = tag.track() {
-                    //     println!("track: {}", track)
-                    // }
-                    // TYER ; 年份
-                    if let Some(year) = tag.year() {
-                        items.push(("TYER".to_string(), "年份".to_string(), year.to_string()))
-                    }
-                    // COMM ; 注释
-                    let mut comment = String::default();
-                    for it in tag.comments() {
-                        comment = comment + &it.text
-                    }
-                    if comment.len() > 0 {
-                        items.push(("COMM".to_string(), "注释".to_string(), comment))
+            Media::LocalFile { lyrics, .. } => lyrics,
+        }
+    }
+    // 重新读取歌词文件
+    pub fn reload_lyrics(&mut self) {
+        match self {
+            Media::LocalFile { path, lyrics, .. } => {
+                if let Some(source) = &lyrics.source {
+                    if let Ok(ref mut file) = std::fs::File::open(source) {
+                        let mut reloaded = Lyrics::from_read(file).with_translation(path);
+                        reloaded.source = lyrics.source.clone();
+                        *lyrics = reloaded;
                     }
-
-                    // 歌词
-                    // for it in tag.lyrics() {}
-
-                    // APIC ; 图片
-                    // for it in tag.pictures() {}
                 }
-
-                items
             }
         }
     }
+    // lrc时间标签
+    pub fn format_lyric_time(time: Duration) -> String {
+        Lyrics::format_time(time)
+    }
+    // 获取标签信息 ; (ID3 帧 ID, 名称, 内容)
+    pub fn get_id3_tag(&self) -> Vec<(String, String, String)> {
+        let metadata = self.get_metadata();
+        let mut items = vec![];
+        let mut push = |id: &str, name: &str, value: String| {
+            items.push((id.to_string(), name.to_string(), value))
+        };
+
+        // TIT2 ; 歌曲标题名字
+        if let Some(title) = &metadata.title {
+            push("TIT2", "标题", title.clone())
+        }
+        // TPE1 ; 主要表演者/独奏者
+        if let Some(artist) = &metadata.artist {
+            push("TPE1", "表演者", artist.clone())
+        }
+        // TALB ; 专辑/电影/节目名称
+        if let Some(album) = &metadata.album {
+            push("TALB", "专辑", album.clone())
+        }
+        // TPE2 ; 乐队/管弦乐团/伴奏
+        if let Some(album_artist) = &metadata.album_artist {
+            push("TPE2", "专辑集艺术家", album_artist.clone())
+        }
+        // TCOM ; 作曲家
+        if let Some(composer) = &metadata.composer {
+            push("TCOM", "作曲家", composer.clone())
+        }
+        // TRCK ; 曲目编号/集合中位置
+        if let Some(track) = metadata.track {
+            push(
+                "TRCK",
+                "曲目",
+                Self::number_pair(track, metadata.total_tracks),
+            )
+        }
+        // TPOS ; 集数
+        if let Some(disc) = metadata.disc {
+            push(
+                "TPOS",
+                "集数",
+                Self::number_pair(disc, metadata.total_discs),
+            )
+        }
+        // TCON ; 内容类型, 如流派、风格等
+        if let Some(genre) = &metadata.genre {
+            push("TCON", "流派", genre.clone())
+        }
+        // TYER ; 年份
+        if let Some(year) = metadata.year {
+            push("TYER", "年份", year.to_string())
+        }
+        // TXXX ; 回放增益
+        if let Some(gain) = metadata.replay_gain.track_gain {
+            push("TXXX", "音轨增益", format!("{:+.2} dB", gain))
+        }
+        if let Some(gain) = metadata.replay_gain.album_gain {
+            push("TXXX", "专辑增益", format!("{:+.2} dB", gain))
+        }
+        // COMM ; 注释
+        let comment = metadata.comments.concat();
+        if !comment.is_empty() {
+            push("COMM", "注释", comment)
+        }
+
+        items
+    }
+    // 3/12
+    fn number_pair(number: u32, total: Option<u32>) -> String {
+        match total {
+            Some(total) => format!("{}/{}", number, total),
+            None => number.to_string(),
+        }
+    }
 }
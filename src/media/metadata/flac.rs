@@ -0,0 +1,158 @@
+use std::{
+    fs,
+    io::{self, Cursor, Read, Seek, SeekFrom},
+    path::Path,
+    time::Duration,
+};
+
+use super::{read_bytes, vorbis, Metadata, TagField, TagFormat};
+
+// 元数据块类型
+const BLOCK_STREAMINFO: u8 = 0;
+const BLOCK_VORBIS_COMMENT: u8 = 4;
+const BLOCK_PICTURE: u8 = 6;
+
+// 文件头有 ID3v2 标签时，判断标签后面是否为 flac
+pub fn is_flac<R: Read + Seek>(r: &mut R) -> bool {
+    let is_flac = skip_id3(r).is_ok() && {
+        let mut magic = [0u8; 4];
+        r.read_exact(&mut magic).is_ok() && &magic == b"fLaC"
+    };
+    let _ = r.seek(SeekFrom::Start(0));
+    is_flac
+}
+
+// 跳过文件头的 ID3v2 标签
+fn skip_id3<R: Read + Seek>(r: &mut R) -> io::Result<()> {
+    let mut header = [0u8; 10];
+    r.read_exact(&mut header)?;
+    if &header[0..3] != b"ID3" {
+        r.seek(SeekFrom::Start(0))?;
+        return Ok(());
+    }
+    // syncsafe integer
+    let size = header[6..10]
+        .iter()
+        .fold(0u64, |size, &b| (size << 7) | (b & 0x7f) as u64);
+    r.seek(SeekFrom::Current(size as i64))?;
+    Ok(())
+}
+
+pub fn read<R: Read + Seek>(r: &mut R) -> Option<Metadata> {
+    let mut metadata = Metadata::default();
+    read_blocks(r, &mut metadata).ok()?;
+    // flac 只使用 Vorbis comment 保存标签
+    metadata.format = Some(TagFormat::Vorbis);
+    Some(metadata)
+}
+
+// 读取所有元数据块 ; 每个块头 1bit 是否为最后一块 + 7bit 类型 + 24bit 长度
+fn read_blocks<R: Read + Seek>(r: &mut R, metadata: &mut Metadata) -> io::Result<()> {
+    skip_id3(r)?;
+    let mut magic = [0u8; 4];
+    r.read_exact(&mut magic)?;
+    if &magic != b"fLaC" {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not a flac file",
+        ));
+    }
+    loop {
+        let mut header = [0u8; 4];
+        r.read_exact(&mut header)?;
+        let is_last = header[0] & 0x80 != 0;
+        let block_type = header[0] & 0x7f;
+        let len = u32::from_be_bytes([0, header[1], header[2], header[3]]) as u64;
+        match block_type {
+            BLOCK_STREAMINFO => {
+                let data = read_bytes(r, len)?;
+                metadata.duration = stream_duration(&data);
+            }
+            BLOCK_VORBIS_COMMENT => {
+                let data = read_bytes(r, len)?;
+                vorbis::read_comments(&mut data.as_slice(), metadata)?;
+            }
+            BLOCK_PICTURE => {
+                let data = read_bytes(r, len)?;
+                metadata
+                    .pictures
+                    .push(vorbis::read_picture(&mut data.as_slice())?);
+            }
+            _ => {
+                r.seek(SeekFrom::Current(len as i64))?;
+            }
+        }
+        if is_last {
+            return Ok(());
+        }
+    }
+}
+
+// 修改 VORBIS_COMMENT 块 ; 重新写入整个文件，先写临时文件再替换
+pub fn write(path: &Path, edits: &[(TagField, String)]) -> io::Result<()> {
+    let data = fs::read(path)?;
+    let mut r = Cursor::new(&data);
+    skip_id3(&mut r)?;
+    let prefix = r.position() as usize;
+    let mut magic = [0u8; 4];
+    r.read_exact(&mut magic)?;
+    if &magic != b"fLaC" {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not a flac file",
+        ));
+    }
+
+    // 读取所有块，音频数据保持不变
+    let mut blocks = vec![];
+    loop {
+        let mut header = [0u8; 4];
+        r.read_exact(&mut header)?;
+        let len = u32::from_be_bytes([0, header[1], header[2], header[3]]) as u64;
+        blocks.push((header[0] & 0x7f, read_bytes(&mut r, len)?));
+        if header[0] & 0x80 != 0 {
+            break;
+        }
+    }
+    let audio = &data[r.position() as usize..];
+
+    match blocks.iter_mut().find(|(t, _)| *t == BLOCK_VORBIS_COMMENT) {
+        Some((_, block)) => *block = vorbis::write_comments(block, edits)?,
+        None => blocks.push((BLOCK_VORBIS_COMMENT, vorbis::write_comments(&[], edits)?)),
+    }
+
+    let mut out = Vec::with_capacity(data.len());
+    out.extend_from_slice(&data[..prefix]);
+    out.extend_from_slice(b"fLaC");
+    let count = blocks.len();
+    for (i, (block_type, block)) in blocks.into_iter().enumerate() {
+        if block.len() >= 1 << 24 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "metadata block too large",
+            ));
+        }
+        let last = if i + 1 == count { 0x80 } else { 0 };
+        let len = (block.len() as u32).to_be_bytes();
+        out.extend_from_slice(&[last | block_type, len[1], len[2], len[3]]);
+        out.extend_from_slice(&block);
+    }
+    out.extend_from_slice(audio);
+
+    let temp = path.with_extension("flac.tmp");
+    fs::write(&temp, out)?;
+    fs::rename(&temp, path)
+}
+
+// STREAMINFO ; 第10字节开始: 20bit 采样率 + 3bit 声道数 + 5bit 位深 + 36bit 总采样数
+fn stream_duration(data: &[u8]) -> Option<Duration> {
+    let bits = u64::from_be_bytes(data.get(10..18)?.try_into().ok()?);
+    let sample_rate = bits >> 44;
+    let total_samples = bits & ((1 << 36) - 1);
+    if sample_rate == 0 || total_samples == 0 {
+        return None;
+    }
+    Some(Duration::from_secs_f64(
+        total_samples as f64 / sample_rate as f64,
+    ))
+}
@@ -0,0 +1,286 @@
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+    time::Duration,
+};
+
+use id3::{
+    frame::{Lyrics, PictureType, TimestampFormat},
+    Tag, TagLike, Version,
+};
+
+use super::{Metadata, Picture, TagField, TagFormat};
+
+// 读取 ID3v2 标签，没有时读取 ID3v1 标签
+pub fn read(path: &Path) -> Option<Metadata> {
+    if let Ok(tag) = Tag::read_from_path(path) {
+        let mut metadata = from_tag(&tag, TagFormat::Id3v2);
+        read_synced_lyrics(&tag, path, &mut metadata);
+        Some(metadata)
+    } else {
+        let tag = id3::v1::Tag::read_from_path(path).ok()?;
+        Some(from_tag(&tag.into(), TagFormat::Id3v1))
+    }
+}
+
+// 写入 ID3v2.4 标签 ; 保留没有修改的帧
+// 没有 ID3v2 标签时从 ID3v1 标签开始，标签无法解析时不写入，避免丢失封面、回放增益等帧
+pub fn write(path: &Path, edits: &[(TagField, String)]) -> io::Result<()> {
+    let mut tag = match id3::no_tag_ok(Tag::read_from_path(path)) {
+        Ok(Some(tag)) => tag,
+        Ok(None) => id3::v1::Tag::read_from_path(path)
+            .map(Tag::from)
+            .unwrap_or_default(),
+        Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+    };
+    for (field, value) in edits {
+        let value = value.trim();
+        match field {
+            TagField::Title if value.is_empty() => tag.remove_title(),
+            TagField::Title => tag.set_title(value),
+            TagField::Artist if value.is_empty() => tag.remove_artist(),
+            TagField::Artist => tag.set_artist(value),
+            TagField::Album if value.is_empty() => tag.remove_album(),
+            TagField::Album => tag.set_album(value),
+            TagField::Genre if value.is_empty() => tag.remove_genre(),
+            TagField::Genre => tag.set_genre(value),
+            TagField::Track => match value.parse() {
+                Ok(track) => tag.set_track(track),
+                Err(_) => tag.remove_track(),
+            },
+            TagField::Year => match value.parse() {
+                Ok(year) => tag.set_year(year),
+                Err(_) => tag.remove_year(),
+            },
+            TagField::Lyrics => {
+                tag.remove_all_lyrics();
+                if !value.is_empty() {
+                    tag.add_frame(Lyrics {
+                        lang: "eng".to_string(),
+                        description: String::new(),
+                        text: value.to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    // wav、aiff 的标签保存在文件内的块中 ; 由id3根据文件格式处理
+    tag.write_to_path(path, Version::Id3v24)
+        .map_err(io::Error::other)
+}
+
+fn from_tag(tag: &Tag, format: TagFormat) -> Metadata {
+    let mut metadata = Metadata {
+        format: Some(format),
+        title: tag.title().map(String::from),
+        artist: tag.artist().map(String::from),
+        album: tag.album().map(String::from),
+        album_artist: tag.album_artist().map(String::from),
+        composer: tag
+            .get("TCOM")
+            .and_then(|frame| frame.content().text())
+            .map(String::from),
+        track: tag.track(),
+        total_tracks: tag.total_tracks(),
+        disc: tag.disc(),
+        total_discs: tag.total_discs(),
+        year: tag.year().or_else(|| tag.date_recorded().map(|it| it.year)),
+        genre: tag.genre_parsed().map(|it| it.to_string()),
+        comments: tag
+            .comments()
+            .map(|it| it.text.clone())
+            .filter(|it| !it.is_empty())
+            .collect(),
+        lyrics: tag
+            .lyrics()
+            .map(|it| it.text.clone())
+            .filter(|it| !it.is_empty())
+            .collect(),
+        pictures: tag
+            .pictures()
+            .map(|it| Picture {
+                front_cover: it.picture_type == PictureType::CoverFront,
+                data: it.data.clone(),
+            })
+            .collect(),
+        ..Default::default()
+    };
+
+    // TXXX ; 回放增益
+    for it in tag.extended_texts() {
+        metadata.set_replay_gain(&it.description, &it.value);
+    }
+
+    metadata
+}
+
+// SYLT ; 使用第一个有内容的帧
+fn read_synced_lyrics(tag: &Tag, path: &Path, metadata: &mut Metadata) {
+    let Some(lyrics) = tag.synchronised_lyrics().find(|it| !it.content.is_empty()) else {
+        return;
+    };
+    // 时间单位为毫秒或mpeg帧
+    let unit = match lyrics.timestamp_format {
+        TimestampFormat::Ms => Duration::from_millis(1),
+        TimestampFormat::Mpeg => match mpeg_frame_duration(path) {
+            Some(d) => d,
+            None => return,
+        },
+    };
+    metadata.synced_lyrics = lyrics
+        .content
+        .iter()
+        .map(|(time, text)| (unit * *time, text.clone()))
+        .collect();
+}
+
+// 第一个mpeg帧的播放时长 ; 每帧的采样数 / 采样率
+fn mpeg_frame_duration(path: &Path) -> Option<Duration> {
+    let mut file = File::open(path).ok()?;
+    let mut header = [0u8; 10];
+    file.read_exact(&mut header).ok()?;
+    // 跳过 ID3v2 标签
+    let start = if &header[..3] == b"ID3" {
+        let size = header[6..10]
+            .iter()
+            .fold(0u64, |size, &b| (size << 7) | (b & 0x7f) as u64);
+        let footer = if header[5] & 0x10 != 0 { 10 } else { 0 };
+        10 + size + footer
+    } else {
+        0
+    };
+    file.seek(SeekFrom::Start(start)).ok()?;
+    let mut data = vec![];
+    file.take(64 * 1024).read_to_end(&mut data).ok()?;
+
+    data.windows(4).find_map(|b| {
+        if b[0] != 0xff || b[1] & 0xe0 != 0xe0 {
+            return None;
+        }
+        // 版本 ; 0: MPEG2.5 2: MPEG2 3: MPEG1
+        let version = (b[1] >> 3) & 0x03;
+        // 层 ; 1: Layer III 2: Layer II 3: Layer I
+        let layer = (b[1] >> 1) & 0x03;
+        let rate_index = (b[2] >> 2) & 0x03;
+        if version == 1 || layer == 0 || rate_index == 3 {
+            return None;
+        }
+        let sample_rate = [44100, 48000, 32000][rate_index as usize]
+            >> match version {
+                3 => 0,
+                2 => 1,
+                _ => 2,
+            };
+        let samples = match (version, layer) {
+            (_, 3) => 384,
+            (3, _) | (_, 2) => 1152,
+            _ => 576,
+        };
+        Some(Duration::from_secs_f64(samples as f64 / sample_rate as f64))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use id3::frame::{
+        Comment, ExtendedText, Picture as Apic, SynchronisedLyrics, SynchronisedLyricsType,
+    };
+
+    use super::*;
+    use crate::utils;
+
+    // mpeg 帧
+    fn audio() -> Vec<u8> {
+        [0xff, 0xfb, 0x90, 0x00].repeat(16)
+    }
+
+    // 有封面、回放增益、注释和同步歌词的 mp3
+    fn tagged_mp3(name: &str) -> PathBuf {
+        let mut tag = Tag::new();
+        tag.set_title("So What");
+        tag.add_frame(Apic {
+            mime_type: "image/png".to_string(),
+            picture_type: PictureType::CoverFront,
+            description: String::new(),
+            data: vec![1, 2, 3],
+        });
+        tag.add_frame(ExtendedText {
+            description: "REPLAYGAIN_TRACK_GAIN".to_string(),
+            value: "-6.50 dB".to_string(),
+        });
+        tag.add_frame(Comment {
+            lang: "eng".to_string(),
+            description: String::new(),
+            text: "comment".to_string(),
+        });
+        tag.add_frame(SynchronisedLyrics {
+            lang: "eng".to_string(),
+            timestamp_format: TimestampFormat::Ms,
+            content_type: SynchronisedLyricsType::Lyrics,
+            description: String::new(),
+            content: vec![(1000, "line".to_string())],
+        });
+        let path = utils::temp_file(name, &audio());
+        tag.write_to_path(&path, Version::Id3v24).unwrap();
+        path
+    }
+
+    #[test]
+    fn keeps_unmodified_frames() {
+        let path = tagged_mp3("keep.mp3");
+        write(&path, &[(TagField::Artist, "Miles Davis".to_string())]).unwrap();
+        let tag = Tag::read_from_path(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(tag.title(), Some("So What"));
+        assert_eq!(tag.artist(), Some("Miles Davis"));
+        assert_eq!(tag.pictures().count(), 1);
+        assert_eq!(tag.extended_texts().count(), 1);
+        assert_eq!(tag.comments().count(), 1);
+        assert_eq!(tag.synchronised_lyrics().count(), 1);
+    }
+
+    #[test]
+    fn unreadable_tag_is_not_overwritten() {
+        let path = tagged_mp3("broken.mp3");
+        // 未知的标签头标记
+        let mut data = fs::read(&path).unwrap();
+        data[5] = 0x01;
+        fs::write(&path, &data).unwrap();
+        let result = write(&path, &[(TagField::Artist, "Miles Davis".to_string())]);
+        let after = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(after, data);
+    }
+
+    #[test]
+    fn starts_from_id3v1_without_id3v2() {
+        let mut v1 = vec![0u8; 128];
+        v1[0..3].copy_from_slice(b"TAG");
+        v1[3..10].copy_from_slice(b"So What");
+        v1[127] = 255;
+        let mut data = audio();
+        data.extend(v1);
+        let path = utils::temp_file("v1.mp3", &data);
+        write(&path, &[(TagField::Artist, "Miles Davis".to_string())]).unwrap();
+        let tag = Tag::read_from_path(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(tag.title(), Some("So What"));
+        assert_eq!(tag.artist(), Some("Miles Davis"));
+    }
+
+    #[test]
+    fn empty_value_removes_field() {
+        let path = tagged_mp3("remove.mp3");
+        write(&path, &[(TagField::Title, " ".to_string())]).unwrap();
+        let tag = Tag::read_from_path(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(tag.title(), None);
+        assert_eq!(tag.pictures().count(), 1);
+    }
+}
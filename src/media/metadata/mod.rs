@@ -0,0 +1,293 @@
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+    time::Duration,
+};
+
+use crate::config::Config;
+
+mod flac;
+mod id3tag;
+mod mp4;
+mod ogg;
+mod vorbis;
+
+// 标签格式
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TagFormat {
+    Id3v1,  // mp3 ID3v1
+    Id3v2,  // mp3 ID3v2
+    Vorbis, // flac、ogg、opus 的 Vorbis comment
+    Mp4,    // mp4、m4a 的 ilst atom
+}
+
+// 图片
+#[derive(Clone)]
+pub struct Picture {
+    pub front_cover: bool, // 是否为封面
+    pub data: Vec<u8>,     // 图片数据 ; jpeg、png
+}
+
+// 回放增益 ; 单位 dB
+#[derive(Clone, Default)]
+pub struct ReplayGain {
+    pub track_gain: Option<f32>,
+    pub track_peak: Option<f32>,
+    pub album_gain: Option<f32>,
+    pub album_peak: Option<f32>,
+}
+
+// 可以编辑的标签字段
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TagField {
+    Title,
+    Artist,
+    Album,
+    Track,
+    Genre,
+    Year,
+    Lyrics,
+}
+
+impl TagField {
+    pub const ALL: [TagField; 7] = [
+        TagField::Title,
+        TagField::Artist,
+        TagField::Album,
+        TagField::Track,
+        TagField::Genre,
+        TagField::Year,
+        TagField::Lyrics,
+    ];
+
+    // 字段名称 ; 与 ALL 的顺序一致
+    pub fn name(&self) -> &'static str {
+        Config::TAG_FIELD_NAMES[*self as usize]
+    }
+
+    // 字段的值 ; 没有时为空字符串
+    pub fn value(&self, metadata: &Metadata) -> String {
+        match self {
+            TagField::Title => metadata.title.clone().unwrap_or_default(),
+            TagField::Artist => metadata.artist.clone().unwrap_or_default(),
+            TagField::Album => metadata.album.clone().unwrap_or_default(),
+            TagField::Track => metadata.track.map(|it| it.to_string()).unwrap_or_default(),
+            TagField::Genre => metadata.genre.clone().unwrap_or_default(),
+            TagField::Year => metadata.year.map(|it| it.to_string()).unwrap_or_default(),
+            TagField::Lyrics => metadata.lyrics.concat(),
+        }
+    }
+}
+
+// 与文件格式无关的歌曲标签
+#[derive(Clone, Default)]
+pub struct Metadata {
+    pub format: Option<TagFormat>,    // 标签格式 ; 没有读取到标签时为None
+    pub title: Option<String>,        // 标题
+    pub artist: Option<String>,       // 表演者
+    pub album: Option<String>,        // 专辑
+    pub album_artist: Option<String>, // 专辑艺术家
+    pub composer: Option<String>,     // 作曲家
+    pub track: Option<u32>,           // 曲目编号
+    pub total_tracks: Option<u32>,    // 曲目总数
+    pub disc: Option<u32>,            // 集数
+    pub total_discs: Option<u32>,     // 总集数
+    pub year: Option<i32>,            // 年份
+    pub genre: Option<String>,        // 流派
+    pub comments: Vec<String>,        // 注释
+    pub lyrics: Vec<String>,          // 内嵌歌词
+    pub synced_lyrics: Vec<(Duration, String)>, // 内嵌同步歌词 ; ID3 SYLT
+    pub pictures: Vec<Picture>,       // 内嵌图片
+    pub replay_gain: ReplayGain,      // 回放增益
+    pub duration: Option<Duration>,   // 文件头中记录的播放时长 ; flac、ogg、mp4
+}
+
+impl Metadata {
+    // 单个标签字段的最大长度 ; 防止损坏的文件申请过大的内存
+    const MAX_FIELD_SIZE: u64 = 64 * 1024 * 1024;
+
+    // 根据文件头读取标签 ; 没有标签时 format 为None
+    pub fn read_from_path(path: &Path) -> Option<Self> {
+        let mut file = File::open(path).ok()?;
+        let mut magic = [0u8; 12];
+        file.read_exact(&mut magic).ok()?;
+        file.seek(SeekFrom::Start(0)).ok()?;
+
+        if &magic[0..4] == b"fLaC" || (&magic[0..3] == b"ID3" && flac::is_flac(&mut file)) {
+            flac::read(&mut file)
+        } else if &magic[0..4] == b"OggS" {
+            ogg::read(&mut file)
+        } else if &magic[4..8] == b"ftyp" {
+            mp4::read(&mut file)
+        } else {
+            id3tag::read(path)
+        }
+    }
+
+    // 写入标签 ; flac 使用 Vorbis comment，mp3、wav、aiff 使用 ID3v2 ; 值为空时删除字段
+    // 其他格式不能写入 ID3，例如 MPEG-TS 和 aac
+    pub fn write_to_path(path: &Path, edits: &[(TagField, String)]) -> io::Result<()> {
+        let mut file = File::open(path)?;
+        let mut magic = [0u8; 12];
+        file.read_exact(&mut magic)?;
+        file.seek(SeekFrom::Start(0))?;
+
+        if &magic[0..4] == b"fLaC" || (&magic[0..3] == b"ID3" && flac::is_flac(&mut file)) {
+            drop(file);
+            flac::write(path, edits)
+        } else if Self::accepts_id3(path, &magic) {
+            drop(file);
+            id3tag::write(path, edits)
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "writing tags to this format is not supported",
+            ))
+        }
+    }
+
+    // 可以写入 ID3v2 的格式 ; mp3 按后缀判断，wav、aiff 按文件头判断，id3 按文件头写入对应的块
+    fn accepts_id3(path: &Path, magic: &[u8; 12]) -> bool {
+        let wav = &magic[0..4] == b"RIFF" && &magic[8..12] == b"WAVE";
+        let aiff = &magic[0..4] == b"FORM" && matches!(&magic[8..12], b"AIFF" | b"AIFC");
+        let mp3 = path
+            .extension()
+            .and_then(|it| it.to_str())
+            .is_some_and(|it| it.eq_ignore_ascii_case("mp3"));
+        wav || aiff || mp3
+    }
+
+    // 封面 ; 优先使用 front cover
+    pub fn cover(&self) -> Option<&Picture> {
+        self.pictures
+            .iter()
+            .find(|it| it.front_cover)
+            .or(self.pictures.first())
+    }
+
+    // 解析 "3/12" 格式的编号
+    fn parse_number_pair(value: &str) -> (Option<u32>, Option<u32>) {
+        let mut parts = value.splitn(2, '/');
+        let number = parts.next().and_then(|it| it.trim().parse().ok());
+        let total = parts.next().and_then(|it| it.trim().parse().ok());
+        (number, total)
+    }
+
+    // 解析日期中的年份 ; 2001-05-12 => 2001
+    fn parse_year(value: &str) -> Option<i32> {
+        value.trim().get(0..4).and_then(|it| it.parse().ok())
+    }
+
+    // 解析回放增益 ; "-6.52 dB" => -6.52
+    fn parse_gain(value: &str) -> Option<f32> {
+        value
+            .trim()
+            .trim_end_matches("dB")
+            .trim_end_matches("db")
+            .trim()
+            .parse()
+            .ok()
+    }
+
+    // 设置回放增益字段
+    fn set_replay_gain(&mut self, key: &str, value: &str) -> bool {
+        let gain = &mut self.replay_gain;
+        let field = match key.to_uppercase().as_str() {
+            "REPLAYGAIN_TRACK_GAIN" => &mut gain.track_gain,
+            "REPLAYGAIN_TRACK_PEAK" => &mut gain.track_peak,
+            "REPLAYGAIN_ALBUM_GAIN" => &mut gain.album_gain,
+            "REPLAYGAIN_ALBUM_PEAK" => &mut gain.album_peak,
+            _ => return false,
+        };
+        *field = Self::parse_gain(value);
+        true
+    }
+}
+
+// 读取指定长度的数据
+fn read_bytes<R: Read>(r: &mut R, len: u64) -> io::Result<Vec<u8>> {
+    if len > Metadata::MAX_FIELD_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "field too large",
+        ));
+    }
+    let mut buf = vec![0u8; len as usize];
+    r.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn read_u32_be<R: Read>(r: &mut R) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_be_bytes(buf))
+}
+
+fn read_u32_le<R: Read>(r: &mut R) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+// 读取指定长度的字符串
+fn read_string<R: Read>(r: &mut R, len: u64) -> io::Result<String> {
+    Ok(String::from_utf8_lossy(&read_bytes(r, len)?).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::utils;
+
+    fn title(value: &str) -> Vec<(TagField, String)> {
+        vec![(TagField::Title, value.to_string())]
+    }
+
+    // 只有 fmt 块的 wav
+    fn wav() -> Vec<u8> {
+        let mut data = b"RIFF".to_vec();
+        data.extend_from_slice(&28u32.to_le_bytes());
+        data.extend_from_slice(b"WAVEfmt ");
+        data.extend_from_slice(&16u32.to_le_bytes());
+        data.extend_from_slice(&[0; 16]);
+        data
+    }
+
+    #[test]
+    fn id3_is_not_written_to_other_containers() {
+        // MPEG-TS 同步字节和 ADTS 帧头
+        for (name, data) in [
+            ("tag.ts", [0x47, 0x40, 0x00, 0x10].repeat(47)),
+            ("tag.aac", [0xff, 0xf1, 0x50, 0x80].repeat(16)),
+        ] {
+            let path = utils::temp_file(name, &data);
+            let result = Metadata::write_to_path(&path, &title("a"));
+            let after = fs::read(&path).unwrap();
+            fs::remove_file(&path).unwrap();
+            assert_eq!(result.unwrap_err().kind(), io::ErrorKind::Unsupported);
+            assert_eq!(after, data);
+        }
+    }
+
+    #[test]
+    fn id3_is_written_to_mp3_and_wav() {
+        let path = utils::temp_file("tag.mp3", &[0xff, 0xfb, 0x90, 0x00].repeat(16));
+        Metadata::write_to_path(&path, &title("So What")).unwrap();
+        let metadata = Metadata::read_from_path(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(metadata.format, Some(TagFormat::Id3v2));
+        assert_eq!(metadata.title.as_deref(), Some("So What"));
+
+        // 后缀不是 wav 时按文件头判断
+        let path = utils::temp_file("tag.wave", &wav());
+        Metadata::write_to_path(&path, &title("Blue")).unwrap();
+        let data = fs::read(&path).unwrap();
+        let metadata = Metadata::read_from_path(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(&data[0..4], b"RIFF");
+        assert_eq!(metadata.title.as_deref(), Some("Blue"));
+    }
+}
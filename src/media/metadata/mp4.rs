@@ -0,0 +1,292 @@
+use std::{
+    io::{self, Read, Seek, SeekFrom},
+    time::Duration,
+};
+
+use id3::{Tag, TagLike};
+
+use super::{read_bytes, read_u32_be, Metadata, Picture, TagFormat};
+
+// atom ; https://developer.apple.com/documentation/quicktime-file-format
+struct Atom {
+    kind: [u8; 4], // atom 类型
+    start: u64,    // 数据开始位置
+    end: u64,      // 数据结束位置
+}
+
+// data atom 的数据类型
+const DATA_UTF8: u32 = 1;
+const DATA_JPEG: u32 = 13;
+const DATA_PNG: u32 = 14;
+
+pub fn read<R: Read + Seek>(r: &mut R) -> Option<Metadata> {
+    let file_end = r.seek(SeekFrom::End(0)).ok()?;
+    let moov = find_child(r, 0, file_end, b"moov")?;
+    let mut metadata = Metadata {
+        duration: find_child(r, moov.start, moov.end, b"mvhd")
+            .and_then(|mvhd| movie_duration(r, &mvhd).ok()),
+        ..Default::default()
+    };
+
+    let meta = match find_child(r, moov.start, moov.end, b"udta") {
+        Some(udta) => find_child(r, udta.start, udta.end, b"meta"),
+        None => None,
+    }
+    .or_else(|| find_child(r, moov.start, moov.end, b"meta"));
+    // meta 是 full atom，前4字节为版本和标记
+    if let Some(ilst) = meta.and_then(|meta| find_child(r, meta.start + 4, meta.end, b"ilst")) {
+        metadata.format = Some(TagFormat::Mp4);
+        for item in children(r, ilst.start, ilst.end) {
+            let _ = read_item(r, &item, &mut metadata);
+        }
+    }
+    Some(metadata)
+}
+
+// mvhd ; 版本0时时间为4字节，版本1时为8字节
+fn movie_duration<R: Read + Seek>(r: &mut R, mvhd: &Atom) -> io::Result<Duration> {
+    r.seek(SeekFrom::Start(mvhd.start))?;
+    let version = read_u32_be(r)? >> 24;
+    let (timescale, duration) = if version == 1 {
+        read_bytes(r, 16)?;
+        let timescale = read_u32_be(r)?;
+        let duration = (read_u32_be(r)? as u64) << 32 | read_u32_be(r)? as u64;
+        (timescale, duration)
+    } else {
+        read_bytes(r, 8)?;
+        (read_u32_be(r)?, read_u32_be(r)? as u64)
+    };
+    if timescale == 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "bad timescale"));
+    }
+    Ok(Duration::from_secs_f64(duration as f64 / timescale as f64))
+}
+
+// 读取 ilst 中的一个标签
+fn read_item<R: Read + Seek>(r: &mut R, item: &Atom, metadata: &mut Metadata) -> io::Result<()> {
+    if &item.kind == b"----" {
+        return read_freeform(r, item, metadata);
+    }
+    for (data_type, value) in data_atoms(r, item)? {
+        let text = || String::from_utf8_lossy(&value).to_string();
+        match &item.kind {
+            b"\xa9nam" => metadata.title = Some(text()),
+            b"\xa9ART" => metadata.artist = Some(text()),
+            b"\xa9alb" => metadata.album = Some(text()),
+            b"aART" => metadata.album_artist = Some(text()),
+            b"\xa9wrt" => metadata.composer = Some(text()),
+            b"\xa9day" => metadata.year = Metadata::parse_year(&text()),
+            b"\xa9gen" => metadata.genre = Some(text()),
+            b"gnre" => metadata.genre = metadata.genre.take().or(genre(&value)),
+            b"\xa9cmt" => metadata.comments.push(text()),
+            b"\xa9lyr" => metadata.lyrics.push(text()),
+            b"trkn" => (metadata.track, metadata.total_tracks) = number_pair(&value),
+            b"disk" => (metadata.disc, metadata.total_discs) = number_pair(&value),
+            b"covr" if data_type == DATA_JPEG || data_type == DATA_PNG => {
+                metadata.pictures.push(Picture {
+                    front_cover: true,
+                    data: value,
+                })
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// 自定义标签 ; mean + name + data，回放增益保存在这里
+fn read_freeform<R: Read + Seek>(
+    r: &mut R,
+    item: &Atom,
+    metadata: &mut Metadata,
+) -> io::Result<()> {
+    let mut name = String::default();
+    for child in children(r, item.start, item.end) {
+        if &child.kind == b"name" {
+            // full atom
+            r.seek(SeekFrom::Start(child.start + 4))?;
+            let data = read_bytes(r, child.end.saturating_sub(child.start + 4))?;
+            name = String::from_utf8_lossy(&data).to_string();
+        }
+    }
+    for (data_type, value) in data_atoms(r, item)? {
+        if data_type == DATA_UTF8 {
+            metadata.set_replay_gain(&name, &String::from_utf8_lossy(&value));
+        }
+    }
+    Ok(())
+}
+
+// 读取所有 data atom ; 4字节类型 + 4字节语言 + 数据
+fn data_atoms<R: Read + Seek>(r: &mut R, item: &Atom) -> io::Result<Vec<(u32, Vec<u8>)>> {
+    let mut values = vec![];
+    for child in children(r, item.start, item.end) {
+        if &child.kind == b"data" && child.end >= child.start + 8 {
+            r.seek(SeekFrom::Start(child.start))?;
+            let data_type = read_u32_be(r)? & 0x00ff_ffff;
+            read_u32_be(r)?;
+            values.push((data_type, read_bytes(r, child.end - child.start - 8)?));
+        }
+    }
+    Ok(values)
+}
+
+// trkn、disk ; 2字节保留 + 2字节编号 + 2字节总数
+fn number_pair(value: &[u8]) -> (Option<u32>, Option<u32>) {
+    let read = |i: usize| {
+        value
+            .get(i..i + 2)
+            .map(|it| u16::from_be_bytes([it[0], it[1]]) as u32)
+            .filter(|&it| it > 0)
+    };
+    (read(2), read(4))
+}
+
+// gnre ; ID3v1 流派编号 + 1
+fn genre(value: &[u8]) -> Option<String> {
+    let index = u16::from_be_bytes([*value.first()?, *value.get(1)?]);
+    let mut tag = Tag::new();
+    tag.set_genre(format!("({})", index.checked_sub(1)?));
+    tag.genre_parsed().map(|it| it.to_string())
+}
+
+fn find_child<R: Read + Seek>(r: &mut R, start: u64, end: u64, kind: &[u8; 4]) -> Option<Atom> {
+    children(r, start, end)
+        .into_iter()
+        .find(|it| &it.kind == kind)
+}
+
+// [start, end) 中的所有子 atom
+fn children<R: Read + Seek>(r: &mut R, start: u64, end: u64) -> Vec<Atom> {
+    let mut atoms = vec![];
+    let mut pos = start;
+    while pos + 8 <= end {
+        match read_atom(r, pos, end) {
+            Ok(atom) => {
+                pos = atom.end;
+                atoms.push(atom);
+            }
+            Err(_) => break,
+        }
+    }
+    atoms
+}
+
+// atom 头 ; 4字节长度 + 4字节类型，长度为1时后面是8字节长度，长度为0时到父 atom 结束
+fn read_atom<R: Read + Seek>(r: &mut R, pos: u64, parent_end: u64) -> io::Result<Atom> {
+    r.seek(SeekFrom::Start(pos))?;
+    let size = read_u32_be(r)? as u64;
+    let mut kind = [0u8; 4];
+    r.read_exact(&mut kind)?;
+    let (header_len, size) = match size {
+        0 => (8, parent_end.saturating_sub(pos)),
+        1 => {
+            let mut buf = [0u8; 8];
+            r.read_exact(&mut buf)?;
+            (16, u64::from_be_bytes(buf))
+        }
+        size => (8, size),
+    };
+    // 长度来自文件，可能溢出
+    let end = match pos.checked_add(size) {
+        Some(end) if size >= header_len && end <= parent_end => end,
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "bad atom size")),
+    };
+    Ok(Atom {
+        kind,
+        start: pos + header_len,
+        end,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn atom(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut data = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+        data.extend_from_slice(kind);
+        data.extend_from_slice(body);
+        data
+    }
+
+    fn title(text: &str) -> Vec<u8> {
+        let mut data = DATA_UTF8.to_be_bytes().to_vec();
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(text.as_bytes());
+        atom(b"\xa9nam", &atom(b"data", &data))
+    }
+
+    fn file(ilst: &[u8]) -> Vec<u8> {
+        let mut meta = vec![0; 4];
+        meta.extend(atom(b"ilst", ilst));
+        let mut data = atom(b"ftyp", b"M4A ");
+        data.extend(atom(b"moov", &atom(b"udta", &atom(b"meta", &meta))));
+        data
+    }
+
+    #[test]
+    fn reads_title() {
+        let metadata = read(&mut Cursor::new(file(&title("So What")))).unwrap();
+        assert_eq!(metadata.format, Some(TagFormat::Mp4));
+        assert_eq!(metadata.title.as_deref(), Some("So What"));
+    }
+
+    #[test]
+    fn truncated_atom_is_skipped() {
+        let mut data = file(&title("So What"));
+        data.truncate(data.len() - 3);
+        assert!(read(&mut Cursor::new(data)).is_none());
+
+        // 只有一半的 atom 头
+        let mut data = atom(b"ftyp", b"M4A ");
+        data.extend_from_slice(&[0, 0, 0]);
+        assert!(read(&mut Cursor::new(data)).is_none());
+    }
+
+    #[test]
+    fn child_larger_than_parent_is_rejected() {
+        let mut ilst = title("So What");
+        // 第二个标签的长度超过 ilst
+        let mut bad = atom(b"\xa9ART", b"");
+        bad[..4].copy_from_slice(&1000u32.to_be_bytes());
+        ilst.extend(bad);
+        let metadata = read(&mut Cursor::new(file(&ilst))).unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("So What"));
+        assert_eq!(metadata.artist, None);
+
+        let data = atom(b"moov", &[0; 8]);
+        assert!(read_atom(&mut Cursor::new(&data), 0, 8).is_err());
+    }
+
+    #[test]
+    fn size_smaller_than_header_is_rejected() {
+        let mut data = atom(b"moov", &[0; 8]);
+        data[..4].copy_from_slice(&4u32.to_be_bytes());
+        assert!(read_atom(&mut Cursor::new(&data), 0, data.len() as u64).is_err());
+    }
+
+    #[test]
+    fn huge_64bit_size_does_not_overflow() {
+        let mut data = atom(b"ftyp", b"");
+        data.extend_from_slice(&1u32.to_be_bytes());
+        data.extend_from_slice(b"moov");
+        data.extend_from_slice(&u64::MAX.to_be_bytes());
+        let end = data.len() as u64;
+        assert!(read_atom(&mut Cursor::new(&data), 8, end).is_err());
+        // 8 + u64::MAX 溢出
+        assert!(read_atom(&mut Cursor::new(&data), 8, u64::MAX).is_err());
+        assert!(read(&mut Cursor::new(data)).is_none());
+    }
+
+    #[test]
+    fn zero_size_extends_to_parent_end() {
+        let mut data = atom(b"mdat", &[0; 8]);
+        data[..4].copy_from_slice(&0u32.to_be_bytes());
+        let end = data.len() as u64 + 4;
+        let atom = read_atom(&mut Cursor::new(&data), 0, end).unwrap();
+        assert_eq!((atom.start, atom.end), (8, end));
+    }
+}
@@ -0,0 +1,46 @@
+use std::{
+    io::{Read, Seek, SeekFrom},
+    time::Duration,
+};
+
+use crate::media::ogg::{self, PacketReader};
+
+use super::{vorbis, Metadata};
+
+// 读取 Vorbis / Opus 的 identification header 和 comment header
+pub fn read<R: Read + Seek>(r: &mut R) -> Option<Metadata> {
+    let mut metadata = Metadata::default();
+    let mut packets = PacketReader::new(&mut *r);
+    let mut headers = vec![];
+    while headers.len() < 2 {
+        headers.extend(packets.next_packets().ok()??);
+    }
+
+    // 采样率和开头需要丢弃的采样数
+    let ident = &headers[0];
+    let (sample_rate, pre_skip) = if ident.starts_with(b"\x01vorbis") && ident.len() >= 16 {
+        (u32::from_le_bytes(ident[12..16].try_into().ok()?), 0)
+    } else if ident.starts_with(b"OpusHead") && ident.len() >= 12 {
+        // opus 固定使用 48kHz 计算位置
+        (48000, u16::from_le_bytes([ident[10], ident[11]]) as u64)
+    } else {
+        return None;
+    };
+
+    let comments = &headers[1];
+    if let Some(comments) = comments
+        .strip_prefix(b"\x03vorbis")
+        .or_else(|| comments.strip_prefix(b"OpusTags"))
+    {
+        let _ = vorbis::read_comments(&mut &comments[..], &mut metadata);
+    }
+
+    // 最后一页的采样位置即为总采样数
+    if r.seek(SeekFrom::Start(0)).is_ok() && sample_rate > 0 {
+        if let Some(granule) = ogg::last_granule_position(r) {
+            let samples = granule.saturating_sub(pre_skip);
+            metadata.duration = Some(Duration::from_secs_f64(samples as f64 / sample_rate as f64));
+        }
+    }
+    Some(metadata)
+}
@@ -0,0 +1,132 @@
+use std::io::{self, Cursor, Read};
+
+use crate::utils;
+
+use super::{
+    read_bytes, read_string, read_u32_be, read_u32_le, Metadata, Picture, TagField, TagFormat,
+};
+
+// 解析 Vorbis comment ; flac 的 VORBIS_COMMENT 块、ogg 的 comment header 共用
+pub fn read_comments<R: Read>(r: &mut R, metadata: &mut Metadata) -> io::Result<()> {
+    let vendor_len = read_u32_le(r)?;
+    read_bytes(r, vendor_len as u64)?;
+    let count = read_u32_le(r)?;
+    for _ in 0..count {
+        let len = read_u32_le(r)?;
+        let comment = read_string(r, len as u64)?;
+        if let Some((key, value)) = comment.split_once('=') {
+            apply_comment(metadata, key, value);
+        }
+    }
+    metadata.format = Some(TagFormat::Vorbis);
+    Ok(())
+}
+
+// 修改 Vorbis comment ; 删除字段的所有旧值后写入新值
+pub fn write_comments(data: &[u8], edits: &[(TagField, String)]) -> io::Result<Vec<u8>> {
+    let mut r = data;
+    let (vendor, mut comments) = if data.is_empty() {
+        (b"rust-player".to_vec(), vec![])
+    } else {
+        let vendor_len = read_u32_le(&mut r)?;
+        let vendor = read_bytes(&mut r, vendor_len as u64)?;
+        let count = read_u32_le(&mut r)?;
+        let mut comments = vec![];
+        for _ in 0..count {
+            let len = read_u32_le(&mut r)?;
+            comments.push(read_bytes(&mut r, len as u64)?);
+        }
+        (vendor, comments)
+    };
+
+    for (field, value) in edits {
+        let keys = comment_keys(*field);
+        comments.retain(|it| {
+            let key = it.split(|&b| b == b'=').next().unwrap_or_default();
+            !keys.contains(&String::from_utf8_lossy(key).to_uppercase().as_str())
+        });
+        let value = value.trim();
+        if !value.is_empty() {
+            comments.push(format!("{}={}", keys[0], value).into_bytes());
+        }
+    }
+
+    let mut out = vec![];
+    out.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    out.extend_from_slice(&vendor);
+    out.extend_from_slice(&(comments.len() as u32).to_le_bytes());
+    for it in comments {
+        out.extend_from_slice(&(it.len() as u32).to_le_bytes());
+        out.extend_from_slice(&it);
+    }
+    Ok(out)
+}
+
+// 字段对应的 comment 名称 ; 第一个用于写入
+fn comment_keys(field: TagField) -> &'static [&'static str] {
+    match field {
+        TagField::Title => &["TITLE"],
+        TagField::Artist => &["ARTIST"],
+        TagField::Album => &["ALBUM"],
+        TagField::Track => &["TRACKNUMBER"],
+        TagField::Genre => &["GENRE"],
+        TagField::Year => &["DATE", "YEAR"],
+        TagField::Lyrics => &["LYRICS", "UNSYNCEDLYRICS"],
+    }
+}
+
+// 字段名不区分大小写 ; https://xiph.org/vorbis/doc/v-comment.html
+fn apply_comment(metadata: &mut Metadata, key: &str, value: &str) {
+    let value = value.to_string();
+    match key.to_uppercase().as_str() {
+        "TITLE" => metadata.title = Some(value),
+        "ARTIST" => metadata.artist = Some(value),
+        "ALBUM" => metadata.album = Some(value),
+        "ALBUMARTIST" | "ALBUM ARTIST" => metadata.album_artist = Some(value),
+        "COMPOSER" => metadata.composer = Some(value),
+        "TRACKNUMBER" => {
+            let (track, total) = Metadata::parse_number_pair(&value);
+            metadata.track = track;
+            metadata.total_tracks = metadata.total_tracks.or(total);
+        }
+        "TRACKTOTAL" | "TOTALTRACKS" => metadata.total_tracks = value.trim().parse().ok(),
+        "DISCNUMBER" => {
+            let (disc, total) = Metadata::parse_number_pair(&value);
+            metadata.disc = disc;
+            metadata.total_discs = metadata.total_discs.or(total);
+        }
+        "DISCTOTAL" | "TOTALDISCS" => metadata.total_discs = value.trim().parse().ok(),
+        "DATE" | "YEAR" => metadata.year = Metadata::parse_year(&value),
+        "GENRE" => metadata.genre = Some(value),
+        "COMMENT" | "DESCRIPTION" => metadata.comments.push(value),
+        "LYRICS" | "UNSYNCEDLYRICS" => metadata.lyrics.push(value),
+        "METADATA_BLOCK_PICTURE" => {
+            let picture = utils::base64_decode(&value)
+                .and_then(|data| read_picture(&mut Cursor::new(data)).ok());
+            if let Some(picture) = picture {
+                metadata.pictures.push(picture);
+            }
+        }
+        key => {
+            metadata.set_replay_gain(key, &value);
+        }
+    }
+}
+
+// 解析 flac 的 PICTURE 块 ; ogg 中以 base64 编码保存在 METADATA_BLOCK_PICTURE 中
+pub fn read_picture<R: Read>(r: &mut R) -> io::Result<Picture> {
+    let picture_type = read_u32_be(r)?;
+    // 图片类型、描述
+    let mime_len = read_u32_be(r)?;
+    read_bytes(r, mime_len as u64)?;
+    let description_len = read_u32_be(r)?;
+    read_bytes(r, description_len as u64)?;
+    // 宽、高、色深、索引颜色数
+    read_bytes(r, 16)?;
+    let data_len = read_u32_be(r)?;
+    let data = read_bytes(r, data_len as u64)?;
+    Ok(Picture {
+        front_cover: picture_type == 3,
+        data,
+    })
+}
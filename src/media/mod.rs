@@ -1,9 +1,20 @@
 pub mod media;
 pub mod player;
 pub mod play_item;
+pub mod decoder;
+pub mod subtitle;
+pub mod playlist;
+pub mod stats;
+mod loader;
 mod lyrics;
+mod metadata;
+mod ogg;
+#[cfg(feature = "opus")]
+mod opus;
 
+pub use loader::Loader;
 pub use player::Player;
 pub use media::Media;
 pub use play_item::PlayItem;
+pub use metadata::{Metadata, TagField};
 
@@ -0,0 +1,93 @@
+use std::io::{self, Read, Seek, SeekFrom};
+
+// ogg 页 ; https://xiph.org/ogg/doc/framing.html
+struct Page {
+    serial: u32,       // 逻辑流编号
+    segments: Vec<u8>, // 分段表
+    data: Vec<u8>,     // 页数据
+}
+
+impl Page {
+    // 读取一页 ; 文件结束时返回None
+    fn read<R: Read>(r: &mut R) -> io::Result<Option<Self>> {
+        let mut header = [0u8; 27];
+        match r.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        if &header[0..4] != b"OggS" {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "bad ogg page"));
+        }
+        let serial = u32::from_le_bytes(header[14..18].try_into().unwrap_or_default());
+        // 最多255个分段，每个分段最多255字节
+        let mut segments = vec![0u8; header[26] as usize];
+        r.read_exact(&mut segments)?;
+        let mut data = vec![0u8; segments.iter().map(|&it| it as usize).sum()];
+        r.read_exact(&mut data)?;
+        Ok(Some(Self {
+            serial,
+            segments,
+            data,
+        }))
+    }
+}
+
+// 按数据包读取第一个逻辑流
+pub struct PacketReader<R: Read> {
+    reader: R,
+    serial: Option<u32>, // 第一个逻辑流的编号
+    packet: Vec<u8>,     // 未读取完成的数据包
+}
+
+impl<R: Read> PacketReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            serial: None,
+            packet: vec![],
+        }
+    }
+
+    // 读取下一页的所有完整数据包 ; 文件结束时返回None
+    pub fn next_packets(&mut self) -> io::Result<Option<Vec<Vec<u8>>>> {
+        loop {
+            let Some(page) = Page::read(&mut self.reader)? else {
+                return Ok(None);
+            };
+            let serial = *self.serial.get_or_insert(page.serial);
+            if page.serial != serial {
+                continue;
+            }
+            let mut packets = vec![];
+            let mut offset = 0;
+            for &len in &page.segments {
+                let end = offset + len as usize;
+                self.packet.extend_from_slice(&page.data[offset..end]);
+                offset = end;
+                // 长度小于255的分段表示数据包结束
+                if len < 255 {
+                    packets.push(std::mem::take(&mut self.packet));
+                }
+            }
+            return Ok(Some(packets));
+        }
+    }
+}
+
+// 最后一页的采样位置 ; 只读取文件末尾的数据
+pub fn last_granule_position<R: Read + Seek>(r: &mut R) -> Option<u64> {
+    // 一页最大约 64KB
+    const TAIL_SIZE: u64 = 65536 + 27 + 255;
+    let len = r.seek(SeekFrom::End(0)).ok()?;
+    r.seek(SeekFrom::Start(len.saturating_sub(TAIL_SIZE)))
+        .ok()?;
+    let mut tail = vec![];
+    r.read_to_end(&mut tail).ok()?;
+    (0..tail.len().saturating_sub(27))
+        .rev()
+        .filter(|&i| &tail[i..i + 4] == b"OggS")
+        .map(|i| u64::from_le_bytes(tail[i + 6..i + 14].try_into().unwrap_or_default()))
+        // -1 表示这一页没有数据包结束
+        .find(|&granule| granule != u64::MAX)
+}
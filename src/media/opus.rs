@@ -0,0 +1,126 @@
+use std::{collections::VecDeque, io, time::Duration};
+
+use opus::{Channels, Decoder};
+use rodio::Source;
+
+use super::ogg::PacketReader;
+
+// ogg opus 解码 ; https://datatracker.ietf.org/doc/html/rfc7845
+pub struct OpusDecoder<R: io::Read> {
+    packets: PacketReader<R>,
+    decoder: Decoder,
+    channels: u16,
+    pending: VecDeque<Vec<u8>>, // 等待解码的数据包
+    buffer: Vec<i16>,           // 已解码的采样
+    position: usize,            // buffer 中下一个采样的位置
+    pre_skip: usize,            // 开头需要丢弃的采样数
+}
+
+impl<R: io::Read> OpusDecoder<R> {
+    // opus 固定输出 48kHz
+    const SAMPLE_RATE: u32 = 48000;
+    // 单个数据包最长 120ms
+    const MAX_FRAME_SIZE: usize = 5760;
+
+    pub fn new(reader: R) -> io::Result<Self> {
+        let mut packets = PacketReader::new(reader);
+        let mut pending = VecDeque::new();
+        while pending.len() < 2 {
+            match packets.next_packets()? {
+                Some(it) => pending.extend(it),
+                None => return Err(invalid_data("missing opus header")),
+            }
+        }
+
+        // OpusHead ; 版本(1) 声道数(1) pre-skip(2)
+        let head = pending.pop_front().unwrap_or_default();
+        if !head.starts_with(b"OpusHead") || head.len() < 19 {
+            return Err(invalid_data("missing opus header"));
+        }
+        let channels = head[9] as u16;
+        let pre_skip = u16::from_le_bytes([head[10], head[11]]) as usize;
+        // OpusTags
+        pending.pop_front();
+
+        let decoder_channels = match channels {
+            1 => Channels::Mono,
+            2 => Channels::Stereo,
+            _ => return Err(invalid_data("unsupported opus channel count")),
+        };
+        let decoder = Decoder::new(Self::SAMPLE_RATE, decoder_channels)
+            .map_err(|e| invalid_data(&e.to_string()))?;
+
+        Ok(Self {
+            packets,
+            decoder,
+            channels,
+            pending,
+            buffer: vec![],
+            position: 0,
+            pre_skip: pre_skip * channels as usize,
+        })
+    }
+
+    // 解码下一个数据包 ; 结束时返回false
+    fn decode_next(&mut self) -> bool {
+        loop {
+            let packet = match self.pending.pop_front() {
+                Some(packet) => packet,
+                None => match self.packets.next_packets() {
+                    Ok(Some(packets)) => {
+                        self.pending.extend(packets);
+                        continue;
+                    }
+                    _ => return false,
+                },
+            };
+            self.buffer
+                .resize(Self::MAX_FRAME_SIZE * self.channels as usize, 0);
+            let Ok(samples) = self.decoder.decode(&packet, &mut self.buffer, false) else {
+                continue;
+            };
+            self.buffer.truncate(samples * self.channels as usize);
+            let skip = self.pre_skip.min(self.buffer.len());
+            self.pre_skip -= skip;
+            self.position = skip;
+            if self.position < self.buffer.len() {
+                return true;
+            }
+        }
+    }
+}
+
+impl<R: io::Read> Iterator for OpusDecoder<R> {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        if self.position >= self.buffer.len() && !self.decode_next() {
+            return None;
+        }
+        let sample = self.buffer[self.position];
+        self.position += 1;
+        Some(sample)
+    }
+}
+
+impl<R: io::Read> Source for OpusDecoder<R> {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        Self::SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
@@ -16,6 +16,9 @@ enum PlayStatus {
 #[derive(Clone)]
 pub struct PlayItem {
     pub media: Media,
+    pub marked: bool,   // 是否被标记 ; 用于批量操作
+    pub missing: bool,  // 文件是否已经被删除
+    pub counted: bool,  // 本次播放是否已经记录到播放统计
     status: PlayStatus, // 播放状态
 }
 
@@ -23,11 +26,15 @@ impl PlayItem {
     pub fn new(media: Media) -> Self {
         Self {
             media,
+            marked: false,
+            missing: false,
+            counted: false,
             status: PlayStatus::Wait(Duration::from_secs(0)),
         }
     }
     pub fn play(&mut self) {
         self.status = PlayStatus::Playing(Instant::now(), Duration::from_secs(0));
+        self.counted = false;
     }
     pub fn play_offset(&mut self, d: Duration) {
         self.status = PlayStatus::Playing(Instant::now(), d);
@@ -44,16 +51,29 @@ impl PlayItem {
             self.status = PlayStatus::Playing(Instant::now(), d);
         }
     }
-    // 前进
+    // 前进 ; 时长未知时不限制
     pub fn forward(&mut self) {
         let max_postion = self.media.get_duration();
 
         self.pause();
         if let PlayStatus::Wait(d) = self.status {
-            let d = cmp::min(max_postion, d.add(Config::FORWARD_AND_REVERSE_STEP));
+            let d = d.add(Config::FORWARD_AND_REVERSE_STEP);
+            let d = if max_postion.is_zero() {
+                d
+            } else {
+                cmp::min(max_postion, d)
+            };
             self.status = PlayStatus::Wait(d);
         }
     }
+    // 停止 ; 回到开头
+    pub fn stop(&mut self) {
+        self.status = PlayStatus::Wait(Duration::from_secs(0));
+    }
+    // 跳转到指定位置 ; 跳转后处于暂停状态
+    pub fn seek(&mut self, d: Duration) {
+        self.status = PlayStatus::Wait(d);
+    }
     // 后退
     pub fn reverse(&mut self) {
         self.pause();
@@ -73,6 +93,27 @@ impl PlayItem {
         (current, self.media.get_duration())
     }
 
+    // 是否达到记录播放的时长 ; 播放超过一半或超过 Config::PLAY_COUNT_DURATION
+    pub fn reached_play_count(&self) -> bool {
+        let (current, total) = self.progress();
+        let threshold = if total.is_zero() {
+            Config::PLAY_COUNT_DURATION
+        } else {
+            cmp::min(total / 2, Config::PLAY_COUNT_DURATION)
+        };
+        current >= threshold
+    }
+
+    // 切换歌曲时是否算作一次跳过 ; 已经开始播放，还没有记录播放
+    pub fn is_skipped(&self) -> bool {
+        !self.counted && !self.progress().0.is_zero()
+    }
+
+    // 播放到结尾或无法播放 ; 之后切换歌曲不算跳过
+    pub fn finish(&mut self) {
+        self.stop();
+    }
+
     pub fn is_playing(&self) -> bool {
         if let PlayStatus::Playing(_, _) = self.status {
             true
@@ -81,3 +122,38 @@ impl PlayItem {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn item() -> PlayItem {
+        PlayItem::new(Media::new_local_file_placeholder(PathBuf::from("/music/a.mp3")).unwrap())
+    }
+
+    #[test]
+    fn switching_before_the_threshold_is_a_skip() {
+        let mut item = item();
+        assert!(!item.is_skipped());
+        item.play();
+        item.play_offset(Duration::from_secs(30));
+        assert!(!item.reached_play_count());
+        assert!(item.is_skipped());
+        item.counted = true;
+        assert!(!item.is_skipped());
+    }
+
+    #[test]
+    fn track_end_without_reaching_the_threshold_is_not_a_skip() {
+        // 时长未知时阈值是 Config::PLAY_COUNT_DURATION，较短的歌曲播放完也达不到
+        let mut item = item();
+        item.play();
+        item.play_offset(Config::PLAY_COUNT_DURATION / 2);
+        assert!(!item.reached_play_count());
+        item.finish();
+        assert!(!item.is_skipped());
+        assert!(!item.is_playing());
+    }
+}
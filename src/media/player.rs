@@ -1,8 +1,10 @@
-use std::{fs::File, io::BufReader, time::Duration};
+use std::{io, sync::mpsc::Sender, time::Duration};
 
-use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink, Source};
+use rodio::{source::EmptyCallback, OutputStream, OutputStreamHandle, Sink, Source};
 
-use super::{media::Media, PlayItem};
+use crate::events::AppEvent;
+
+use super::{decoder, media::Media, PlayItem};
 
 pub struct Player {
     _stream: OutputStream,
@@ -10,10 +12,12 @@ pub struct Player {
     sink: Sink,                 // 音轨
     pub current_time: Duration, // 当前播放时间
     pub total_time: Duration,   // 总的播放时间
+    generation: usize,          // 播放序号 ; 每次播放加一，用于忽略已经切换的歌曲的结束事件
+    events: Sender<AppEvent>,   // 发送播放完毕事件
 }
 
 impl Player {
-    pub fn new() -> Self {
+    pub fn new(events: Sender<AppEvent>) -> Self {
         let (stream, stream_handle) = OutputStream::try_default().unwrap();
         let sink = Sink::try_new(&stream_handle).unwrap();
 
@@ -21,6 +25,8 @@ impl Player {
             current_time: Duration::from_secs(0),
             total_time: Duration::from_secs(0),
             sink,
+            generation: 0,
+            events,
             _stream: stream,
             _stream_handle: stream_handle,
         }
@@ -36,27 +42,39 @@ impl Player {
         self.sink.volume()
     }
 
-    // 添加播放源
-    fn source(&mut self, play_item: &PlayItem, duration: Duration) {
+    // 添加播放源 ; 不支持的格式或损坏的文件返回错误
+    fn source(&mut self, play_item: &PlayItem, duration: Duration) -> io::Result<()> {
         match &play_item.media {
             Media::LocalFile { path, .. } => {
-                let file = File::open(path).unwrap();
-                let source = Decoder::new(BufReader::new(file)).unwrap();
+                let source = decoder::open(path)?;
                 self.sink.append(source.skip_duration(duration));
             }
         }
+        // 播放源结束后发送事件
+        let events = self.events.clone();
+        let generation = self.generation;
+        self.sink
+            .append(EmptyCallback::<f32>::new(Box::new(move || {
+                let _ = events.send(AppEvent::TrackEnd(generation));
+            })));
+        Ok(())
     }
 
     // 单音乐播放
-    pub fn play(&mut self, play_item: &PlayItem) {
+    pub fn play(&mut self, play_item: &PlayItem) -> io::Result<()> {
         self.play_offset(play_item, Duration::from_secs(0))
     }
 
     // 指定开始播放位置
-    pub fn play_offset(&mut self, play_item: &PlayItem, duration: Duration) {
-        self.sink.clear();
-        self.source(play_item, duration);
+    pub fn play_offset(&mut self, play_item: &PlayItem, duration: Duration) -> io::Result<()> {
+        self.clear();
+        // 无法播放时也发送结束事件，切换到下一首
+        if let Err(e) = self.source(play_item, duration) {
+            let _ = self.events.send(AppEvent::TrackEnd(self.generation));
+            return Err(e);
+        }
         self.sink.play();
+        Ok(())
     }
 
     // 暂停
@@ -74,8 +92,14 @@ impl Player {
         self.sink.empty()
     }
 
-    // 清空播放
-    pub fn clear(&self) {
+    // 清空播放 ; 之前的播放源不再发送结束事件
+    pub fn clear(&mut self) {
+        self.generation += 1;
         self.sink.clear();
     }
+
+    // 是否为当前播放的结束事件
+    pub fn is_current(&self, generation: usize) -> bool {
+        self.generation == generation
+    }
 }
@@ -0,0 +1,162 @@
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+use crate::{config::Config, utils};
+
+// 保存的播放列表文件夹 ; m3u 格式
+pub fn playlist_dir() -> PathBuf {
+    Config::data_dir().join("playlists")
+}
+
+// 播放列表名称对应的文件
+pub fn playlist_path(name: &str) -> PathBuf {
+    playlist_dir().join(format!("{}.m3u", name))
+}
+
+// 智能播放列表对应的文件 ; 第一行为查找的文件夹，第二行为查询
+pub fn smart_path(name: &str) -> PathBuf {
+    playlist_dir().join(format!("{}.smart", name))
+}
+
+// 所有保存的播放列表名称 ; 按名称排序
+pub fn names() -> Vec<String> {
+    names_with_extension("m3u")
+}
+
+// 所有智能播放列表名称
+pub fn smart_names() -> Vec<String> {
+    names_with_extension("smart")
+}
+
+fn names_with_extension(extension: &str) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(playlist_dir())
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|it| it.path())
+                .filter(|it| it.extension().is_some_and(|ext| ext == extension))
+                .filter_map(|it| Some(it.file_stem()?.to_string_lossy().to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort_by(|a, b| utils::natural_cmp(a, b));
+    names
+}
+
+// 检查新的播放列表名称 ; 不能为空、包含路径分隔符或与已有的播放列表重名
+pub fn check_new_name(name: &str) -> io::Result<PathBuf> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            Config::TIP_PLAYLIST_NAME_INVALID,
+        ));
+    }
+    let path = playlist_path(name);
+    if path.exists() || smart_path(name).exists() {
+        return Err(io::Error::new(
+            ErrorKind::AlreadyExists,
+            Config::TIP_PLAYLIST_EXISTS,
+        ));
+    }
+    Ok(path)
+}
+
+// 新建空的播放列表
+pub fn create(name: &str) -> io::Result<()> {
+    let path = check_new_name(name)?;
+    write(&path, &[])
+}
+
+// 重命名播放列表
+pub fn rename(from: &str, to: &str) -> io::Result<()> {
+    let path = check_new_name(to)?;
+    fs::rename(playlist_path(from), path)
+}
+
+// 复制播放列表
+pub fn duplicate(from: &str, to: &str) -> io::Result<()> {
+    let path = check_new_name(to)?;
+    fs::copy(playlist_path(from), path).map(|_| ())
+}
+
+// 删除播放列表
+pub fn remove(name: &str) -> io::Result<()> {
+    fs::remove_file(playlist_path(name))
+}
+
+// 读取智能播放列表 ; 返回查找的文件夹和查询
+pub fn read_smart(name: &str) -> io::Result<(PathBuf, String)> {
+    let text = fs::read_to_string(smart_path(name))?;
+    let mut lines = text.lines();
+    let root = PathBuf::from(lines.next().unwrap_or_default().trim());
+    let query = lines.next().unwrap_or_default().trim().to_string();
+    Ok((root, query))
+}
+
+// 保存智能播放列表
+pub fn write_smart(name: &str, root: &Path, query: &str) -> io::Result<()> {
+    fs::create_dir_all(playlist_dir())?;
+    fs::write(
+        smart_path(name),
+        format!("{}\n{}\n", root.to_string_lossy(), query),
+    )
+}
+
+// 删除智能播放列表
+pub fn remove_smart(name: &str) -> io::Result<()> {
+    fs::remove_file(smart_path(name))
+}
+
+// 上次使用的播放列表 ; 没有记录时使用默认播放列表
+pub fn active_name() -> String {
+    fs::read_to_string(Config::data_dir().join("active_playlist"))
+        .map(|it| it.trim().to_string())
+        .ok()
+        .filter(|it| !it.is_empty())
+        .unwrap_or(Config::DEFAULT_PLAYLIST.to_string())
+}
+
+// 记录正在使用的播放列表
+pub fn set_active_name(name: &str) -> io::Result<()> {
+    fs::create_dir_all(Config::data_dir())?;
+    fs::write(Config::data_dir().join("active_playlist"), name)
+}
+
+// 读取 m3u 文件 ; 忽略注释，相对路径相对于播放列表所在的文件夹
+pub fn read(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let text = utils::decode_text(&fs::read(path)?);
+    let base = path.parent().unwrap_or(Path::new(""));
+    Ok(text
+        .lines()
+        .map(|it| it.trim())
+        .filter(|it| !it.is_empty() && !it.starts_with('#'))
+        .map(|it| base.join(it))
+        .collect())
+}
+
+// 写入 m3u 文件 ; 使用绝对路径
+pub fn write(path: &Path, files: &[PathBuf]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut text = String::from("#EXTM3U\n");
+    for it in files {
+        text.push_str(&it.to_string_lossy());
+        text.push('\n');
+    }
+    fs::write(path, text)
+}
+
+// 追加到 m3u 文件 ; 已经存在的文件不重复添加
+pub fn append(path: &Path, files: &[PathBuf]) -> io::Result<()> {
+    let mut list = if path.exists() { read(path)? } else { vec![] };
+    for it in files {
+        if !list.contains(it) {
+            list.push(it.clone());
+        }
+    }
+    write(path, &list)
+}
@@ -0,0 +1,330 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::config::Config;
+
+// 一首歌曲的播放统计
+#[derive(Clone, Default, Debug)]
+pub struct Stats {
+    pub hash: u64,                // 文件内容的摘要 ; 文件重命名或移动后用来找回统计
+    pub plays: u32,               // 播放次数
+    pub skips: u32,               // 跳过次数
+    pub rating: Option<u8>,       // 评分 ; 1-5
+    pub favorite: bool,           // 收藏
+    pub last_played: Option<u64>, // 最后播放的时间 ; unix 秒
+}
+
+// 播放统计数据库 ; 保存在数据文件夹中的 stats.tsv，修改后定时写入，退出时写入
+// 每行: 摘要 播放次数 跳过次数 评分 收藏 最后播放时间 路径
+// 路径中的 % 制表符 换行 写成 %XX ; 不是 UTF-8 的路径不保存
+pub struct StatsDb {
+    path: PathBuf,
+    entries: HashMap<PathBuf, Stats>,
+    changed_at: Option<Instant>, // 第一次还没保存的修改的时间
+}
+
+impl StatsDb {
+    // 摘要读取的文件开头和结尾的长度
+    const HASH_SAMPLE: u64 = 64 * 1024;
+
+    pub fn new() -> Self {
+        Self::open(Config::data_dir().join("stats.tsv"))
+    }
+
+    fn open(path: PathBuf) -> Self {
+        let entries = fs::read_to_string(&path)
+            .map(|text| text.lines().filter_map(Self::parse_line).collect())
+            .unwrap_or_default();
+        Self {
+            path,
+            entries,
+            changed_at: None,
+        }
+    }
+
+    fn parse_line(line: &str) -> Option<(PathBuf, Stats)> {
+        let mut fields = line.splitn(7, '\t');
+        let hash = u64::from_str_radix(fields.next()?, 16).ok()?;
+        let plays = fields.next()?.parse().ok()?;
+        let skips = fields.next()?.parse().ok()?;
+        let rating = fields
+            .next()?
+            .parse()
+            .ok()
+            .filter(|it| (1..=5).contains(it));
+        let favorite = fields.next()? == "1";
+        let last_played = fields.next()?.parse().ok();
+        let path = PathBuf::from(Self::unescape(fields.next()?));
+        let stats = Stats {
+            hash,
+            plays,
+            skips,
+            rating,
+            favorite,
+            last_played,
+        };
+        Some((path, stats))
+    }
+
+    // 按路径查找 ; 不计算摘要，用于排序和查询
+    pub fn get(&self, path: &Path) -> Option<&Stats> {
+        self.entries.get(path)
+    }
+
+    // 所有统计 ; 交给后台查询使用
+    pub fn snapshot(&self) -> HashMap<PathBuf, Stats> {
+        self.entries.clone()
+    }
+
+    // 查找或创建统计 ; 路径找不到时按摘要找回重命名之前的统计
+    fn entry(&mut self, path: &Path) -> &mut Stats {
+        if !self.entries.contains_key(path) {
+            let hash = Self::hash(path).unwrap_or_default();
+            let renamed = self
+                .entries
+                .iter()
+                .find(|(old, it)| it.hash == hash && hash != 0 && !old.exists())
+                .map(|(old, _)| old.clone());
+            let stats = match renamed.and_then(|old| self.entries.remove(&old)) {
+                Some(stats) => stats,
+                None => Stats {
+                    hash,
+                    ..Default::default()
+                },
+            };
+            self.entries.insert(path.to_path_buf(), stats);
+        }
+        self.entries.get_mut(path).unwrap()
+    }
+
+    // 找回重命名的文件的统计 ; 开始播放时调用
+    pub fn relink(&mut self, path: &Path) {
+        if !self.entries.contains_key(path) {
+            let stats = self.entry(path).clone();
+            if stats.plays == 0 && stats.skips == 0 && stats.rating.is_none() && !stats.favorite {
+                self.entries.remove(path);
+            }
+        }
+    }
+
+    // 记录一次播放 ; 同时更新摘要，标签修改后文件内容会变化
+    pub fn record_play(&mut self, path: &Path) {
+        let hash = Self::hash(path).unwrap_or_default();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|it| it.as_secs())
+            .ok();
+        let stats = self.entry(path);
+        stats.plays += 1;
+        stats.hash = hash;
+        stats.last_played = now;
+        self.changed();
+    }
+
+    // 记录一次跳过
+    pub fn record_skip(&mut self, path: &Path) {
+        self.entry(path).skips += 1;
+        self.changed();
+    }
+
+    // 设置评分 ; None 清除评分
+    pub fn set_rating(&mut self, path: &Path, rating: Option<u8>) {
+        self.entry(path).rating = rating;
+        self.changed();
+    }
+
+    // 切换收藏
+    pub fn toggle_favorite(&mut self, path: &Path) {
+        let stats = self.entry(path);
+        stats.favorite = !stats.favorite;
+        self.changed();
+    }
+
+    // 合并一段时间内的修改，一起写入
+    fn changed(&mut self) {
+        self.changed_at.get_or_insert_with(Instant::now);
+    }
+
+    // 是否有还没保存的修改
+    pub fn is_dirty(&self) -> bool {
+        self.changed_at.is_some()
+    }
+
+    // 是否到了保存的时间
+    pub fn save_due(&self) -> bool {
+        self.changed_at
+            .is_some_and(|it| it.elapsed() >= Config::STATS_SAVE_DELAY)
+    }
+
+    // 写入还没保存的修改 ; 写入失败时等到下一次修改再重试
+    pub fn save(&mut self) -> io::Result<()> {
+        if self.changed_at.take().is_none() {
+            return Ok(());
+        }
+        self.write()
+    }
+
+    // 写入数据库文件 ; 先写入临时文件再替换，避免写入中断时丢失数据
+    fn write(&self) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut text = String::new();
+        for (path, it) in self.entries.iter() {
+            // 不是 UTF-8 的路径无法原样写入
+            let Some(path) = path.to_str() else {
+                continue;
+            };
+            text.push_str(&format!(
+                "{:016x}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                it.hash,
+                it.plays,
+                it.skips,
+                it.rating.map(|it| it.to_string()).unwrap_or_default(),
+                if it.favorite { 1 } else { 0 },
+                it.last_played.map(|it| it.to_string()).unwrap_or_default(),
+                Self::escape(path)
+            ));
+        }
+        let temp = self.path.with_extension("tsv.tmp");
+        fs::write(&temp, text)?;
+        fs::rename(temp, &self.path)
+    }
+
+    // 转义路径中会破坏文件格式的字符
+    fn escape(path: &str) -> String {
+        let mut text = String::with_capacity(path.len());
+        for c in path.chars() {
+            match c {
+                '%' | '\t' | '\n' | '\r' => text.push_str(&format!("%{:02X}", c as u8)),
+                _ => text.push(c),
+            }
+        }
+        text
+    }
+
+    fn unescape(text: &str) -> String {
+        let mut path = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(i) = rest.find('%') {
+            path.push_str(&rest[..i]);
+            rest = &rest[i + 1..];
+            match rest.get(..2).and_then(|it| u8::from_str_radix(it, 16).ok()) {
+                Some(b) if b.is_ascii() => {
+                    path.push(b as char);
+                    rest = &rest[2..];
+                }
+                _ => path.push('%'),
+            }
+        }
+        path.push_str(rest);
+        path
+    }
+
+    // 文件内容的摘要 ; FNV-1a，只读取文件大小、开头和结尾，避免读取整个文件
+    fn hash(path: &Path) -> io::Result<u64> {
+        let mut file = File::open(path)?;
+        let len = file.metadata()?.len();
+        let mut data = len.to_le_bytes().to_vec();
+        (&mut file).take(Self::HASH_SAMPLE).read_to_end(&mut data)?;
+        if len > Self::HASH_SAMPLE * 2 {
+            file.seek(SeekFrom::End(-(Self::HASH_SAMPLE as i64)))?;
+            file.read_to_end(&mut data)?;
+        }
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for b in data {
+            hash ^= b as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        Ok(hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils;
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let path = utils::temp_path("stats-round-trip.tsv");
+        let mut db = StatsDb::open(path.clone());
+        let plain = PathBuf::from("/music/a.mp3");
+        let tricky = PathBuf::from("/music/100%\tdone\nline\r.mp3");
+        let windows = PathBuf::from("C:\\music\\new %41.mp3");
+        db.set_rating(&plain, Some(4));
+        db.record_skip(&plain);
+        db.toggle_favorite(&tricky);
+        db.record_play(&windows);
+        assert!(db.is_dirty());
+        assert!(!db.save_due());
+        db.save().unwrap();
+        assert!(!db.is_dirty());
+
+        let loaded = StatsDb::open(path.clone());
+        assert_eq!(loaded.entries.len(), 3);
+        let stats = loaded.get(&plain).unwrap();
+        assert_eq!(stats.rating, Some(4));
+        assert_eq!(stats.skips, 1);
+        assert!(!stats.favorite);
+        assert!(loaded.get(&tricky).unwrap().favorite);
+        let stats = loaded.get(&windows).unwrap();
+        assert_eq!(stats.plays, 1);
+        assert_eq!(stats.last_played, db.get(&windows).unwrap().last_played);
+        // 一行一条
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 3);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn save_only_when_changed() {
+        let path = utils::temp_path("stats-unchanged.tsv");
+        let mut db = StatsDb::open(path.clone());
+        db.save().unwrap();
+        assert!(!path.exists());
+        db.toggle_favorite(Path::new("/music/a.mp3"));
+        db.save().unwrap();
+        assert!(path.exists());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn escape_round_trip() {
+        for path in [
+            "plain",
+            "%",
+            "%%25",
+            "a\tb\nc\rd",
+            "%zz %4",
+            "歌曲 %e6.mp3",
+            "%",
+        ] {
+            assert_eq!(StatsDb::unescape(&StatsDb::escape(path)), path);
+            assert!(!StatsDb::escape(path).contains(['\t', '\n', '\r']));
+        }
+        // 旧文件中没有转义的 %
+        assert_eq!(StatsDb::unescape("50% off"), "50% off");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_paths_are_not_saved() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let path = utils::temp_path("stats-non-utf8.tsv");
+        let mut db = StatsDb::open(path.clone());
+        let bad = PathBuf::from(OsStr::from_bytes(b"/music/\xff.mp3"));
+        db.toggle_favorite(&bad);
+        db.toggle_favorite(Path::new("/music/a.mp3"));
+        db.save().unwrap();
+        let loaded = StatsDb::open(path.clone());
+        assert_eq!(loaded.entries.len(), 1);
+        assert!(loaded.get(Path::new("/music/a.mp3")).is_some());
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,190 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use clap::ValueEnum;
+
+use crate::{config::Config, utils};
+
+use super::{
+    lyrics::{Lyric, Lyrics},
+    Media,
+};
+
+// 字幕格式
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum SubtitleFormat {
+    Srt, // SubRip
+    Vtt, // WebVTT ; 支持逐字时间
+}
+
+impl SubtitleFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SubtitleFormat::Srt => "srt",
+            SubtitleFormat::Vtt => "vtt",
+        }
+    }
+}
+
+// 一条字幕
+struct Cue<'a> {
+    start: Duration,
+    end: Duration,
+    lyric: &'a Lyric,
+}
+
+// 把歌词转换为字幕 ; 没有同步歌词时返回None
+pub fn to_subtitle(media: &Media, format: SubtitleFormat) -> Option<String> {
+    let lyrics = media.get_lyrics();
+    let duration = media.get_duration();
+
+    // 结束时间为下一行的开始时间，最后一行为歌曲结束
+    let mut cues = vec![];
+    for (i, it) in lyrics.list.iter().enumerate() {
+        if it.content.trim().is_empty() {
+            continue;
+        }
+        let start = lyrics.playback_position(it.time);
+        let end = match lyrics.list.get(i + 1) {
+            Some(next) => lyrics.playback_position(next.time),
+            None if duration > start => duration,
+            None => start + Config::SUBTITLE_LAST_CUE_DURATION,
+        };
+        if end > start {
+            cues.push(Cue {
+                start,
+                end,
+                lyric: it,
+            });
+        }
+    }
+    if cues.is_empty() {
+        return None;
+    }
+
+    let mut out = String::new();
+    match format {
+        SubtitleFormat::Srt => {
+            for (i, cue) in cues.iter().enumerate() {
+                out.push_str(&format!(
+                    "{}\n{} --> {}\n{}\n",
+                    i + 1,
+                    format_time(cue.start, ','),
+                    format_time(cue.end, ','),
+                    cue.lyric.content.trim()
+                ));
+                if let Some(translation) = &cue.lyric.translation {
+                    out.push_str(translation);
+                    out.push('\n');
+                }
+                out.push('\n');
+            }
+        }
+        SubtitleFormat::Vtt => {
+            out.push_str("WEBVTT\n\n");
+            // 歌曲信息
+            let metadata = media.get_metadata();
+            let notes: Vec<String> = [
+                ("Title", metadata.title.as_ref().or(lyrics.title.as_ref())),
+                (
+                    "Artist",
+                    metadata.artist.as_ref().or(lyrics.artist.as_ref()),
+                ),
+                ("Album", metadata.album.as_ref().or(lyrics.album.as_ref())),
+            ]
+            .into_iter()
+            .filter_map(|(key, value)| value.map(|value| format!("{}: {}", key, value)))
+            .collect();
+            if !notes.is_empty() {
+                out.push_str(&format!("NOTE\n{}\n\n", notes.join("\n")));
+            }
+            for cue in cues.iter() {
+                out.push_str(&format!(
+                    "{} --> {}\n{}\n",
+                    format_time(cue.start, '.'),
+                    format_time(cue.end, '.'),
+                    vtt_text(cue, lyrics)
+                ));
+                if let Some(translation) = &cue.lyric.translation {
+                    out.push_str(&vtt_escape(translation));
+                    out.push('\n');
+                }
+                out.push('\n');
+            }
+        }
+    }
+    Some(out)
+}
+
+// 逐字时间转换为字幕内的时间标签 ; 只保留在字幕时间范围内的标签
+fn vtt_text(cue: &Cue, lyrics: &Lyrics) -> String {
+    if cue.lyric.words.is_empty() {
+        return vtt_escape(cue.lyric.content.trim());
+    }
+    let mut text = String::new();
+    for word in cue.lyric.words.iter() {
+        let time = lyrics.playback_position(word.time);
+        if time > cue.start && time < cue.end {
+            text.push_str(&format!("<{}>", format_time(time, '.')));
+        }
+        text.push_str(&vtt_escape(&word.content));
+    }
+    text.trim().to_string()
+}
+
+fn vtt_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+// hh:mm:ss,mmm 或 hh:mm:ss.mmm
+fn format_time(time: Duration, separator: char) -> String {
+    let ms = time.as_millis();
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        separator,
+        ms % 1000
+    )
+}
+
+// 导出文件或文件夹(包括子文件夹)中所有媒体文件的字幕
+pub fn export(path: &Path, format: SubtitleFormat, output: Option<&Path>) -> io::Result<()> {
+    let files = if path.is_dir() {
+        utils::search_directory(&path.to_path_buf(), ".*")
+    } else {
+        vec![path.to_path_buf()]
+    };
+    if let Some(output) = output {
+        fs::create_dir_all(output)?;
+    }
+
+    for file in files {
+        let Some(media) = Media::new_local_file(file.clone()) else {
+            continue;
+        };
+        let Some(subtitle) = to_subtitle(&media, format) else {
+            println!("{}: {:?}", Config::TIP_NO_SYNCED_LYRICS, file);
+            continue;
+        };
+        let target = subtitle_path(&file, format, output);
+        fs::write(&target, subtitle)?;
+        println!("{:?} -> {:?}", file, target);
+    }
+    Ok(())
+}
+
+// 字幕文件路径 ; 默认和媒体文件在同一个文件夹
+fn subtitle_path(file: &Path, format: SubtitleFormat, output: Option<&Path>) -> PathBuf {
+    let path = match (output, file.file_name()) {
+        (Some(output), Some(name)) => output.join(name),
+        _ => file.to_path_buf(),
+    };
+    path.with_extension(format.extension())
+}
@@ -0,0 +1,110 @@
+use std::{
+    cmp::Ordering, collections::HashMap, path::PathBuf, sync::mpsc::Sender, thread, time::Instant,
+};
+
+use crate::{
+    config::Config,
+    events::AppEvent,
+    media::{stats::Stats, Media, Metadata},
+    utils,
+};
+
+use super::query::{Query, Track};
+
+// 添加到播放列表的方式
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum EnqueueMode {
+    Append,       // 追加到末尾
+    PlayNext,     // 添加到下一首播放
+    Replace,      // 替换整个播放列表
+    Save(String), // 添加到保存的播放列表 ; 播放列表名称
+    Smart(bool),  // 智能播放列表的结果替换播放列表 ; 是否开始播放，否则为自动刷新
+}
+
+// 在后台递归查找文件和文件夹中的媒体文件 ; 完成后发送 AppEvent::Scanned
+// 按曲目排序时需要读取标签，期间发送 AppEvent::ScanProgress
+pub fn scan(paths: Vec<PathBuf>, mode: EnqueueMode, by_track: bool, events: Sender<AppEvent>) {
+    thread::spawn(move || {
+        let mut files = vec![];
+        for path in paths {
+            if path.is_dir() {
+                files.extend(utils::search_directory(&path, ".*"));
+            } else {
+                files.push(path);
+            }
+        }
+        let natural = |a: &PathBuf, b: &PathBuf| {
+            utils::natural_cmp(&a.to_string_lossy(), &b.to_string_lossy())
+        };
+
+        if by_track {
+            // 同一个文件夹内按集数、曲目排序，没有曲目编号的排在最后
+            let total = files.len();
+            let mut tracks = HashMap::new();
+            let mut last_progress = Instant::now();
+            for (i, path) in files.iter().enumerate() {
+                let metadata = Metadata::read_from_path(path).unwrap_or_default();
+                let key = (
+                    metadata.disc.unwrap_or(0),
+                    metadata.track.unwrap_or(u32::MAX),
+                );
+                tracks.insert(path.clone(), key);
+                if last_progress.elapsed() > Config::REFRESH_RATE {
+                    last_progress = Instant::now();
+                    let _ = events.send(AppEvent::ScanProgress(i + 1, total));
+                }
+            }
+            files.sort_by(|a, b| {
+                let ordering = match (a.parent(), b.parent()) {
+                    (Some(x), Some(y)) => natural(&x.to_path_buf(), &y.to_path_buf()),
+                    _ => Ordering::Equal,
+                };
+                ordering
+                    .then_with(|| tracks.get(a).cmp(&tracks.get(b)))
+                    .then_with(|| natural(a, b))
+            });
+        } else {
+            files.sort_by(natural);
+        }
+        let _ = events.send(AppEvent::Scanned(mode, files));
+    });
+}
+
+// 在后台查找文件夹中符合查询的媒体文件 ; 完成后发送 AppEvent::Scanned
+// 需要读取所有文件的标签，期间发送 AppEvent::ScanProgress
+// 播放统计使用开始查找时的副本
+pub fn evaluate(
+    root: PathBuf,
+    query: Query,
+    stats: HashMap<PathBuf, Stats>,
+    mode: EnqueueMode,
+    events: Sender<AppEvent>,
+) {
+    thread::spawn(move || {
+        let files = utils::search_directory(&root, ".*");
+        let total = files.len();
+        let needs_duration = query.needs_duration();
+        let mut matched = vec![];
+        let mut last_progress = Instant::now();
+        for (i, path) in files.into_iter().enumerate() {
+            let metadata = Metadata::read_from_path(&path).unwrap_or_default();
+            let file = path.metadata().ok();
+            let stats = stats.get(&path).cloned().unwrap_or_default();
+            let track = Track {
+                path: &path,
+                metadata: &metadata,
+                duration: needs_duration.then(|| Media::duration(&path, &metadata)),
+                added: file.and_then(|it| it.created().or_else(|_| it.modified()).ok()),
+                stats,
+            };
+            if query.matches(&track) {
+                matched.push(path);
+            }
+            if last_progress.elapsed() > Config::REFRESH_RATE {
+                last_progress = Instant::now();
+                let _ = events.send(AppEvent::ScanProgress(i + 1, total));
+            }
+        }
+        let _ = events.send(AppEvent::Scanned(mode, query.select(matched)));
+    });
+}
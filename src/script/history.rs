@@ -0,0 +1,168 @@
+use std::path::PathBuf;
+
+use crate::config::Config;
+
+// 播放列表和下一首播放中的文件
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Snapshot {
+    pub play_list: Vec<PathBuf>,
+    pub up_next: Vec<PathBuf>,
+    pub current: Option<usize>, // 正在播放的位置 ; 同一首歌曲出现多次时用来区分
+}
+
+impl Snapshot {
+    // 只比较列表 ; 正在播放的位置变化不算修改
+    fn same_lists(&self, other: &Self) -> bool {
+        self.play_list == other.play_list && self.up_next == other.up_next
+    }
+}
+
+// 播放列表的撤销、重做 ; 只保存文件路径，恢复时复用已经解析的媒体信息
+pub struct History {
+    undo: Vec<Snapshot>, // 修改前的播放列表
+    redo: Vec<Snapshot>, // 撤销前的播放列表
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self {
+            undo: vec![],
+            redo: vec![],
+        }
+    }
+
+    // 记录修改前的播放列表 ; 新的修改会清空重做
+    pub fn record(&mut self, state: Snapshot) {
+        self.redo.clear();
+        if self.undo.last().is_some_and(|it| it.same_lists(&state)) {
+            return;
+        }
+        self.undo.push(state);
+        if self.undo.len() > Config::UNDO_LIMIT {
+            self.undo.remove(0);
+        }
+    }
+
+    // 撤销 ; 跳过和当前相同的状态
+    pub fn undo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        while let Some(state) = self.undo.pop() {
+            if !state.same_lists(&current) {
+                self.redo.push(current);
+                return Some(state);
+            }
+        }
+        None
+    }
+
+    // 重做
+    pub fn redo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        while let Some(state) = self.redo.pop() {
+            if !state.same_lists(&current) {
+                self.undo.push(current);
+                return Some(state);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(play_list: &[&str], up_next: &[&str]) -> Snapshot {
+        Snapshot {
+            play_list: play_list.iter().map(PathBuf::from).collect(),
+            up_next: up_next.iter().map(PathBuf::from).collect(),
+            current: None,
+        }
+    }
+
+    #[test]
+    fn undo_and_redo_in_order() {
+        let mut history = History::new();
+        history.record(state(&[], &[]));
+        history.record(state(&["a"], &[]));
+        let current = state(&["a", "b"], &[]);
+
+        let undone = history.undo(current.clone()).unwrap();
+        assert_eq!(undone, state(&["a"], &[]));
+        let undone = history.undo(undone).unwrap();
+        assert_eq!(undone, state(&[], &[]));
+        assert_eq!(history.undo(undone.clone()), None);
+
+        let redone = history.redo(undone).unwrap();
+        assert_eq!(redone, state(&["a"], &[]));
+        let redone = history.redo(redone).unwrap();
+        assert_eq!(redone, current);
+        assert_eq!(history.redo(redone), None);
+    }
+
+    #[test]
+    fn new_record_clears_redo() {
+        let mut history = History::new();
+        history.record(state(&["a"], &[]));
+        let undone = history.undo(state(&["a", "b"], &[])).unwrap();
+        history.record(undone);
+        assert_eq!(history.redo(state(&["a", "c"], &[])), None);
+        assert_eq!(
+            history.undo(state(&["a", "c"], &[])),
+            Some(state(&["a"], &[]))
+        );
+    }
+
+    #[test]
+    fn same_states_are_skipped() {
+        let mut history = History::new();
+        history.record(state(&["a"], &[]));
+        history.record(state(&["a"], &[]));
+        history.record(state(&["a", "b"], &[]));
+        // 和当前相同的状态不算一次撤销
+        let undone = history.undo(state(&["a", "b"], &[])).unwrap();
+        assert_eq!(undone, state(&["a"], &[]));
+        assert_eq!(history.undo(undone), None);
+    }
+
+    #[test]
+    fn up_next_is_part_of_the_state() {
+        let mut history = History::new();
+        history.record(state(&["a"], &[]));
+        let undone = history.undo(state(&["a"], &["b"])).unwrap();
+        assert_eq!(undone, state(&["a"], &[]));
+        assert_eq!(history.redo(undone), Some(state(&["a"], &["b"])));
+    }
+
+    #[test]
+    fn trims_oldest_at_limit() {
+        let mut history = History::new();
+        for i in 0..Config::UNDO_LIMIT + 5 {
+            history.record(state(&[&i.to_string()], &[]));
+        }
+        let mut current = state(&["current"], &[]);
+        let mut count = 0;
+        while let Some(previous) = history.undo(current.clone()) {
+            current = previous;
+            count += 1;
+        }
+        assert_eq!(count, Config::UNDO_LIMIT);
+        assert_eq!(current, state(&["5"], &[]));
+    }
+
+    #[test]
+    fn current_is_kept_but_not_a_change() {
+        let mut history = History::new();
+        let mut before = state(&["a", "b", "a"], &[]);
+        before.current = Some(2);
+        history.record(before.clone());
+        // 只有播放位置不同时不算一次修改
+        let mut moved = before.clone();
+        moved.current = Some(0);
+        history.record(moved.clone());
+        assert_eq!(history.undo(moved), None);
+
+        let mut history = History::new();
+        history.record(before.clone());
+        let undone = history.undo(state(&["a", "b"], &[])).unwrap();
+        assert_eq!(undone.current, Some(2));
+    }
+}
@@ -0,0 +1,75 @@
+use std::{path::PathBuf, time::Duration};
+
+use ratatui::widgets::ListState;
+
+use crate::media::Media;
+
+// 为纯文本歌词逐行打时间标签
+pub struct LyricSync {
+    pub path: PathBuf,                // 正在同步的媒体文件
+    pub lines: Vec<String>,           // 歌词
+    pub times: Vec<Option<Duration>>, // 每行的时间 ; 未标记为None
+    pub index: ListState,             // 当前行
+}
+
+impl LyricSync {
+    pub fn new(path: PathBuf, lines: Vec<String>) -> Self {
+        let mut index = ListState::default();
+        index.select(Some(0));
+        Self {
+            path,
+            times: vec![None; lines.len()],
+            lines,
+            index,
+        }
+    }
+
+    // 当前行
+    pub fn selected(&self) -> usize {
+        self.index.selected().unwrap_or(0)
+    }
+
+    // 标记当前行的时间，并移动到下一行
+    pub fn tap(&mut self, time: Duration) {
+        let i = self.selected();
+        if let Some(it) = self.times.get_mut(i) {
+            *it = Some(time);
+        }
+        self.down();
+    }
+
+    pub fn up(&mut self) {
+        self.index.select(Some(self.selected().saturating_sub(1)));
+    }
+
+    pub fn down(&mut self) {
+        let i = self.selected() + 1;
+        if i < self.lines.len() {
+            self.index.select(Some(i));
+        }
+    }
+
+    // 微调当前行的时间 ; 毫秒
+    pub fn nudge(&mut self, delta: i64) {
+        let i = self.selected();
+        if let Some(Some(time)) = self.times.get_mut(i) {
+            let d = Duration::from_millis(delta.unsigned_abs());
+            *time = if delta >= 0 {
+                *time + d
+            } else {
+                time.saturating_sub(d)
+            };
+        }
+    }
+
+    // 已标记的行 ; 没有标记的行不写入
+    pub fn to_lrc(&self) -> String {
+        self.lines
+            .iter()
+            .zip(self.times.iter())
+            .filter_map(|(line, time)| {
+                time.map(|time| format!("{}{}\n", Media::format_lyric_time(time), line))
+            })
+            .collect()
+    }
+}
@@ -1,30 +1,84 @@
-use std::time::Duration;
+use std::cmp;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ops::RangeInclusive;
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
 use std::{fs::DirEntry, path::PathBuf};
 
 use ratatui::widgets::ListState;
 
 use crate::config::Config;
-use crate::media::{Media, PlayItem, Player};
+use crate::events::AppEvent;
+use crate::media::stats::StatsDb;
+use crate::media::{playlist, Loader, Media, Metadata, PlayItem, Player};
+use crate::utils;
 
+pub use self::enqueue::EnqueueMode;
+pub use self::lyric_sync::LyricSync;
+pub use self::playlists::{PlaylistInput, Playlists};
+pub use self::search::{Search, SearchTarget};
+pub use self::sort::ExplorerSort;
+pub use self::tag_editor::TagEditor;
+
+use self::history::{History, Snapshot};
+use self::query::Query;
+use self::sort::SortMode;
+use self::watcher::DirWatcher;
+
+mod enqueue;
+mod history;
+mod lyric_sync;
+mod playlists;
+mod query;
+mod search;
+mod sort;
+mod tag_editor;
+mod watcher;
+
+// 歌词展示方式
 #[derive(PartialEq, Clone, Copy)]
-enum SortOrder {
-    Asc,  // 升序 ; 默认
-    Desc, // 降序
+pub enum LyricMode {
+    Both,        // 原文和翻译 ; 默认
+    Original,    // 只展示原文
+    Translation, // 只展示翻译 ; 没有翻译的行展示原文
 }
 
 pub struct Script {
-    current_dir: PathBuf,           // 当前目录
-    sort_order: SortOrder,          // 排序方式
-    pub list: Vec<DirEntry>,        // current_dir下的所有目录和文件
-    pub list_index: ListState,      // 列表状态
-    pub current_dir_string: String, // 当前目录 string
-    pub volume: f32,                // 音量大小 ; [0, 1]
-    pub player: Player,             // 音频播放控制器
-    pub play_list: Vec<PlayItem>,   // 播放列表
+    current_dir: PathBuf,            // 当前目录
+    pub sort: ExplorerSort,          // 排序方式
+    pub list: Vec<DirEntry>,         // current_dir下的所有目录和文件
+    pub list_index: ListState,       // 列表状态
+    pub current_dir_string: String,  // 当前目录 string
+    pub volume: f32,                 // 音量大小 ; [0, 1]
+    pub player: Player,              // 音频播放控制器
+    pub play_list: Vec<PlayItem>,    // 播放列表 ; 播放过的歌曲保留在列表中
+    pub current: Option<usize>,      // 正在播放的歌曲在播放列表中的位置 ; 播放完毕时为None
+    pub up_next: VecDeque<PlayItem>, // 下一首播放 ; 优先于播放列表，播放时插入到正在播放的歌曲后面
     pub play_list_index: ListState,
     pub now_playing_name: String,
     pub next_play_name: String,
     pub header_text: String,
+    pub search: Search,                        // 列表搜索
+    pub lyric_mode: LyricMode,                 // 歌词展示方式
+    pub lyric_sync: Option<LyricSync>,         // 纯文本歌词同步 ; 同步模式下有值
+    pub tag_editor: Option<TagEditor>,         // 标签编辑 ; 编辑模式下有值
+    pub lyric_focus: bool,                     // 歌词面板是否获得焦点
+    lyric_cursor: Option<(usize, Instant)>,    // 歌词光标 ; 一段时间没有操作后恢复跟随播放
+    error: Option<(String, Instant)>,          // 错误信息 ; 展示一段时间后清除
+    loader: Loader,                            // 后台解析媒体文件
+    watcher: DirWatcher,                       // 监听目录变化
+    pub scan_progress: Option<(usize, usize)>, // 正在后台添加文件夹 ; 读取标签的进度
+    pub play_list_anchor: Option<usize>,       // 播放列表范围选择的起点
+    pub explorer_anchor: Option<usize>,        // 文件夹列表范围选择的起点
+    pub explorer_marked: HashSet<PathBuf>,     // 文件夹列表中标记的文件和文件夹
+    notice: Option<(String, Instant)>,         // 提示信息 ; 展示一段时间后清除
+    history: History,                          // 播放列表的撤销、重做
+    pub active_playlist: String, // 正在使用的播放列表 ; 播放列表的内容保存在同名文件中
+    pub playlists: Playlists,    // 播放列表管理
+    pub stats: StatsDb,          // 播放次数、评分、收藏
+    smart_source: Option<(String, PathBuf)>, // 来自智能播放列表 ; 名称和查找的文件夹，手动修改后为None
+    smart_refresh_at: Option<Instant>,       // 智能播放列表等待刷新的时间
+    events: Sender<AppEvent>,                // 发送后台任务的事件
 }
 
 // 切换索引
@@ -45,85 +99,267 @@ fn change_list_index<F, F2>(
 }
 
 impl Script {
-    pub fn new() -> Result<Self, std::io::Error> {
+    pub fn new(events: Sender<AppEvent>) -> Result<Self, std::io::Error> {
         let mut list_index = ListState::default();
         list_index.select(Some(0));
-        let player = Player::new();
-        Ok(Self {
+        let player = Player::new(events.clone());
+        let mut this = Self {
             current_dir: std::env::current_dir()?,
             list: vec![],
             list_index,
-            sort_order: SortOrder::Asc,
+            sort: ExplorerSort::new(),
             current_dir_string: String::default(),
             volume: player.volume(),
             player,
             play_list: vec![],
+            current: None,
+            up_next: VecDeque::new(),
             play_list_index: ListState::default(),
             now_playing_name: String::default(),
             next_play_name: String::default(),
             header_text: String::default(),
-        })
+            search: Search::new(),
+            lyric_mode: LyricMode::Both,
+            lyric_sync: None,
+            tag_editor: None,
+            lyric_focus: false,
+            lyric_cursor: None,
+            error: None,
+            loader: Loader::new(events.clone()),
+            watcher: DirWatcher::new(events.clone()),
+            scan_progress: None,
+            play_list_anchor: None,
+            explorer_anchor: None,
+            explorer_marked: HashSet::new(),
+            notice: None,
+            history: History::new(),
+            active_playlist: playlist::active_name(),
+            playlists: Playlists::new(),
+            stats: StatsDb::new(),
+            smart_source: None,
+            smart_refresh_at: None,
+            events,
+        };
+        this.load_dir();
+        this.load_active_playlist();
+        Ok(this)
     }
 
     pub fn set_current_dir(&mut self, path: &PathBuf) {
         self.current_dir = PathBuf::from(path);
+        self.load_dir();
     }
 
     // 初始化脚本
     pub fn init(&mut self) {
         self.current_dir_string = self.current_dir.to_string_lossy().to_string();
-        self.now_playing_name = if let Some(it) = self.play_list.first() {
+        self.now_playing_name = if let Some(it) = self.current() {
             it.media.get_name()
         } else {
             String::default()
         };
-        self.next_play_name = if let Some(it) = self.play_list.get(1) {
+        self.next_play_name = if let Some(it) = self.next_item() {
             it.media.get_name()
         } else {
             String::default()
         };
 
+        // 播放达到一定时长后记录播放次数
+        if self.current().is_some_and(|it| it.is_playing()) {
+            self.count_play();
+        }
+
         // 设置音量
         self.player.set_volume(self.volume);
 
-        // 播放完毕，下一首
+        // 定时保存播放统计
+        if self.stats.save_due() {
+            let result = self.stats.save();
+            self.show_stats_error(result);
+        }
+
+        // 文件变化一段时间后刷新智能播放列表
+        if self.smart_refresh_at.is_some_and(|it| Instant::now() >= it) {
+            self.smart_refresh_at = None;
+            self.refresh_smart();
+        }
+
+        // 歌词光标超时后恢复跟随播放
+        if let Some((_, time)) = &self.lyric_cursor {
+            if time.elapsed() > Config::LYRIC_CURSOR_TIMEOUT {
+                self.lyric_cursor = None;
+            }
+        }
+
+        // 清除过期的错误信息和提示信息
+        if let Some((_, time)) = &self.error {
+            if time.elapsed() > Config::ERROR_MESSAGE_DURATION {
+                self.error = None;
+            }
+        }
+        if let Some((_, time)) = &self.notice {
+            if time.elapsed() > Config::ERROR_MESSAGE_DURATION {
+                self.notice = None;
+            }
+        }
+
         self.header_text = String::from(self.player.is_empty().to_string());
-        if let Some(first) = self.play_list.first() {
-            if first.is_playing() && self.player.is_empty() {
-                self.on_next();
+    }
+
+    // 监听当前目录和播放列表所在的目录 ; 切换目录或修改播放列表后调用
+    fn update_watch(&mut self) {
+        let mut dirs = HashSet::new();
+        dirs.insert(self.current_dir.clone());
+        if let Some((_, root)) = &self.smart_source {
+            dirs.insert(root.clone());
+        }
+        for it in self.play_list.iter().chain(self.up_next.iter()) {
+            if let Some(parent) = it.media.get_path().parent() {
+                dirs.insert(parent.to_path_buf());
             }
         }
+        self.watcher.watch(dirs);
+    }
+
+    // 播放列表有变化 ; 更新监听的目录和播放列表的搜索结果
+    fn play_list_changed(&mut self) {
+        self.update_watch();
+        if self.search.is_active(SearchTarget::PlayList) {
+            self.update_search();
+        }
+    }
 
+    // 读取当前目录 ; 只在切换目录时读取
+    pub fn load_dir(&mut self) {
         self.list.clear();
         let mut dirs = vec![];
         let mut files = vec![];
-        if self.current_dir.is_dir() {
-            for entry in std::fs::read_dir(self.current_dir.clone()).unwrap() {
-                if let Ok(entry) = entry {
-                    if Config::is_accepted_dir(&entry) {
-                        dirs.push(entry);
-                    } else if Config::is_accepted_file(&entry) {
-                        files.push(entry);
-                    }
+        if let Ok(entries) = std::fs::read_dir(&self.current_dir) {
+            for entry in entries.flatten() {
+                if Config::is_accepted_dir(&entry, self.sort.show_hidden) {
+                    dirs.push(entry);
+                } else if Config::is_accepted_file(&entry) {
+                    files.push(entry);
                 }
             }
         }
-        Self::sort(&mut dirs, SortOrder::Asc);
-        Self::sort(&mut files, self.sort_order);
+        // 按时长、曲目排序时在后台解析，完成后重新排序
+        for path in self.sort.sort(&mut dirs, &mut files, &self.stats) {
+            self.loader.load(path);
+        }
         self.list.extend(dirs);
         self.list.extend(files);
+        self.update_watch();
+        self.update_search();
     }
 
-    fn sort(arr: &mut Vec<DirEntry>, sort_order: SortOrder) {
-        arr.sort_by(|a, b| {
-            if sort_order == SortOrder::Asc {
-                a.file_name().cmp(&b.file_name())
-            } else if sort_order == SortOrder::Desc {
-                b.file_name().cmp(&a.file_name())
-            } else {
-                a.file_name().cmp(&b.file_name())
+    // 重新读取当前目录 ; 按名称保持选中项
+    fn reload_dir(&mut self) {
+        let offset = if self.has_parent() { 1 } else { 0 };
+        let selected = self.list_index.selected().unwrap_or(0);
+        let selected_name = selected
+            .checked_sub(offset)
+            .and_then(|i| self.list.get(i))
+            .map(|it| it.file_name());
+        self.load_dir();
+        let index = selected_name
+            .and_then(|name| self.list.iter().position(|it| it.file_name() == name))
+            .map(|i| i + offset)
+            .unwrap_or(cmp::min(
+                selected,
+                (self.list.len() + offset).saturating_sub(1),
+            ));
+        self.list_index.select(Some(index));
+    }
+
+    // 目录中的文件有变化 ; 重新读取当前目录，标记已经删除的歌曲
+    pub fn on_file_changed(&mut self) {
+        let paths = self.watcher.handled();
+        self.reload_dir();
+        for it in self.play_list.iter_mut().chain(self.up_next.iter_mut()) {
+            it.missing = !it.media.get_path().exists();
+        }
+        self.schedule_refresh_smart(&paths);
+    }
+
+    // 定时刷新的间隔 ; 播放中或有需要过期的状态时刷新，否则只在事件到达时刷新
+    pub fn tick_rate(&self) -> Option<Duration> {
+        let playing = self.current().is_some_and(|it| it.is_playing());
+        let expiring = self.error.is_some()
+            || self.notice.is_some()
+            || self.lyric_cursor.is_some()
+            || self.smart_refresh_at.is_some()
+            || self.stats.is_dirty();
+        if playing || expiring {
+            Some(Config::REFRESH_RATE)
+        } else {
+            None
+        }
+    }
+
+    // 刷新搜索结果
+    fn update_search(&mut self) {
+        if self.search.is_active(SearchTarget::Explorer) {
+            // 标签在后台解析，解析完成前只匹配文件名
+            let mut candidates = vec![];
+            for it in &self.list {
+                let path = it.path();
+                let name = it.file_name().to_string_lossy().to_string();
+                if path.is_dir() {
+                    candidates.push(name + "/");
+                } else if let Some(tag_text) = self.search.file_tag_text(&path) {
+                    candidates.push(Search::candidate(&name, tag_text));
+                } else {
+                    if self.search.request(path.clone()) {
+                        self.loader.load(path);
+                    }
+                    candidates.push(name);
+                }
             }
-        })
+            self.search.update(candidates);
+            self.sync_search_selected();
+        } else if self.search.is_active(SearchTarget::Lyrics) {
+            let candidates: Vec<String> = match self.current() {
+                Some(first) => first
+                    .media
+                    .get_lyrics()
+                    .list
+                    .iter()
+                    .map(|it| match &it.translation {
+                        Some(translation) => Search::candidate(&it.content, translation),
+                        None => it.content.clone(),
+                    })
+                    .collect(),
+                None => vec![],
+            };
+            self.search.update(candidates);
+            self.sync_search_selected();
+        } else if self.search.is_active(SearchTarget::PlayList) {
+            let candidates: Vec<String> = self
+                .play_list
+                .iter()
+                .map(|it| {
+                    let tag_text = Search::tag_text(it.media.get_metadata());
+                    Search::candidate(&it.media.get_name(), &tag_text)
+                })
+                .collect();
+            self.search.update(candidates);
+            self.sync_search_selected();
+        }
+    }
+
+    // 将搜索结果的选中项同步到原列表
+    fn sync_search_selected(&mut self) {
+        if let Some(index) = self.search.selected() {
+            match self.search.target {
+                SearchTarget::Explorer => {
+                    let index = if self.has_parent() { index + 1 } else { index };
+                    self.list_index.select(Some(index));
+                }
+                SearchTarget::PlayList => self.play_list_index.select(Some(index)),
+                SearchTarget::Lyrics => self.lyric_cursor = Some((index, Instant::now())),
+            }
+        }
     }
 
     // 当前目录是否存在上一级
@@ -131,28 +367,73 @@ impl Script {
         self.current_dir.parent().is_some()
     }
 
-    // 添加本地媒体文件
+    // 添加本地媒体文件 ; 标签、歌词和时长在后台解析
     pub fn add_local_file_to_play_list(&mut self, path: &PathBuf) {
-        if let Some(media) = Media::new_local_file(PathBuf::from(path)) {
+        if let Some(media) = Media::new_local_file_placeholder(PathBuf::from(path)) {
+            self.record_play_list();
+            self.loader.load(PathBuf::from(path));
             self.play_list.push(PlayItem::new(media));
-            if self.play_list.len() == 1 {
-                self.play();
+            self.play_list_changed();
+            if self.current.is_none() {
+                self.play_at(Some(self.play_list.len() - 1));
             }
         }
     }
 
+    // 正在播放的歌曲
+    pub fn current(&self) -> Option<&PlayItem> {
+        self.current.and_then(|i| self.play_list.get(i))
+    }
+    // 下一首 ; 优先插队的歌曲
+    fn next_item(&self) -> Option<&PlayItem> {
+        match self.current {
+            Some(i) => self.up_next.front().or(self.play_list.get(i + 1)),
+            None => self.up_next.front(),
+        }
+    }
+    // 切换到播放列表中的歌曲 ; 超出播放列表时停止播放
+    // 没有达到记录播放的时长就切换时记录一次跳过 ; 播放结束后自动切换不算
+    fn play_at(&mut self, index: Option<usize>) {
+        let mut skipped = None;
+        if let Some(it) = self.current.and_then(|i| self.play_list.get_mut(i)) {
+            if it.is_skipped() {
+                skipped = Some(it.media.get_path().clone());
+            }
+            it.stop();
+        }
+        if let Some(path) = skipped {
+            self.stats.record_skip(&path);
+        }
+        self.current = index.filter(|i| *i < self.play_list.len());
+        self.play();
+        // 歌词搜索跟随正在播放的歌曲
+        if self.search.is_active(SearchTarget::Lyrics) {
+            self.update_search();
+        }
+    }
+
     // 播放
+    // 无法播放时展示错误信息，播放状态不变，由结束事件切换到下一首
     fn play(&mut self) {
-        if let Some(play_item) = self.play_list.first_mut() {
+        if let Some(play_item) = self.current.and_then(|i| self.play_list.get_mut(i)) {
+            self.stats.relink(play_item.media.get_path());
             play_item.play();
-            self.player.play(play_item);
+            if let Err(e) = self.player.play(play_item) {
+                let name = play_item.media.get_name();
+                self.show_play_error(&name, e);
+            }
         } else {
             self.player.clear();
         }
     }
     fn play_offset(&mut self, d: Duration) {
-        if let Some(play_item) = self.play_list.first_mut() {
-            self.player.play_offset(play_item, d);
+        if let Some(play_item) = self.current.and_then(|i| self.play_list.get_mut(i)) {
+            if let Err(e) = self.player.play_offset(play_item, d) {
+                play_item.play_offset(d);
+                let name = play_item.media.get_name();
+                self.show_play_error(&name, e);
+                return;
+            }
             loop {
                 if !self.player.is_empty() {
                     play_item.play_offset(d);
@@ -164,6 +445,101 @@ impl Script {
         }
     }
 
+    // 记录一次播放 ; 每次播放只记录一次
+    fn count_play(&mut self) {
+        if let Some(it) = self.current.and_then(|i| self.play_list.get_mut(i)) {
+            if !it.counted && it.reached_play_count() {
+                it.counted = true;
+                self.stats.record_play(it.media.get_path());
+            }
+        }
+    }
+    fn show_stats_error(&mut self, result: std::io::Result<()>) {
+        if let Err(e) = result {
+            let message = format!("{}: {}", Config::TIP_STATS_SAVE_FAILED, e);
+            self.error = Some((message, Instant::now()));
+        }
+    }
+    // 给正在播放的歌曲评分 ; None 清除评分
+    pub fn on_set_rating(&mut self, rating: Option<u8>) {
+        if let Some(path) = self.current().map(|it| it.media.get_path().clone()) {
+            self.stats.set_rating(&path, rating);
+        }
+    }
+    // 收藏或取消收藏正在播放的歌曲
+    pub fn on_toggle_favorite(&mut self) {
+        if let Some(path) = self.current().map(|it| it.media.get_path().clone()) {
+            self.stats.toggle_favorite(&path);
+        }
+    }
+
+    // 展示播放失败的错误信息
+    fn show_play_error(&mut self, name: &str, e: std::io::Error) {
+        let message = format!("{} {}: {}", Config::TIP_PLAY_FAILED, name, e);
+        self.error = Some((message, Instant::now()));
+    }
+
+    // 当前的错误信息
+    pub fn error_message(&self) -> Option<&str> {
+        self.error.as_ref().map(|(message, _)| message.as_str())
+    }
+
+    // 当前的提示信息
+    pub fn notice_message(&self) -> Option<&str> {
+        self.notice.as_ref().map(|(message, _)| message.as_str())
+    }
+
+    // 添加到保存的播放列表 ; 正在使用的播放列表直接添加到播放列表中
+    fn save_to_playlist(&mut self, name: &str, files: Vec<PathBuf>) {
+        if files.is_empty() {
+            return;
+        }
+        let result = if name == self.active_playlist {
+            self.record_play_list();
+            for path in files {
+                if let Some(media) = Media::new_local_file_placeholder(path.clone()) {
+                    self.loader.load(path);
+                    self.play_list.push(PlayItem::new(media));
+                }
+            }
+            self.play_list_changed();
+            self.save_active_playlist()
+        } else {
+            playlist::append(&playlist::playlist_path(name), &files)
+        };
+        match result {
+            Ok(()) => {
+                let message = format!("{} {}", Config::TIP_SAVED_TO_PLAYLIST, name);
+                self.notice = Some((message, Instant::now()));
+            }
+            Err(e) => {
+                let message = format!("{}: {}", Config::TIP_PLAYLIST_SAVE_FAILED, e);
+                self.error = Some((message, Instant::now()));
+            }
+        }
+    }
+    // 保存正在使用的播放列表
+    pub fn save_active_playlist(&self) -> std::io::Result<()> {
+        let path = playlist::playlist_path(&self.active_playlist);
+        playlist::write(&path, &self.play_list_paths())
+    }
+    // 读取正在使用的播放列表 ; 正在播放的歌曲在新的播放列表中时继续播放
+    fn load_active_playlist(&mut self) {
+        let path = playlist::playlist_path(&self.active_playlist);
+        let paths = playlist::read(&path).unwrap_or_default();
+        self.smart_source = None;
+        self.restore_play_list(paths, self.current);
+        self.history = History::new();
+        self.play_list_anchor = None;
+        self.play_list_index.select(Some(self.current.unwrap_or(0)));
+    }
+
+    // 范围选择 ; 起点到光标之间
+    fn range(anchor: Option<usize>, cursor: Option<usize>) -> Option<RangeInclusive<usize>> {
+        let (anchor, cursor) = (anchor?, cursor?);
+        Some(cmp::min(anchor, cursor)..=cmp::max(anchor, cursor))
+    }
+
     // 控制音量
     fn on_volume_change(&mut self, value: f32) {
         self.volume = self.volume + value;
@@ -181,25 +557,41 @@ impl Script {
         self.on_volume_change(-0.1)
     }
     pub fn on_list_down(&mut self) {
+        if self.search.is_active(SearchTarget::Explorer) {
+            return self.on_search_next();
+        }
         let len = self.list.len();
         let len = if self.has_parent() { len + 1 } else { len };
         change_list_index(len, &mut self.list_index, |i| i + 1, |_| 0);
     }
     pub fn on_list_up(&mut self) {
+        if self.search.is_active(SearchTarget::Explorer) {
+            return self.on_search_prev();
+        }
         let len = self.list.len();
         let len = if self.has_parent() { len + 1 } else { len };
         change_list_index(len, &mut self.list_index, |i| i - 1, |len| len - 1);
     }
     pub fn on_play_list_down(&mut self) {
+        if self.search.is_active(SearchTarget::PlayList) {
+            return self.on_search_next();
+        }
         let len = self.play_list.len();
         change_list_index(len, &mut self.play_list_index, |i| i + 1, |_| 0);
     }
     pub fn on_play_list_up(&mut self) {
+        if self.search.is_active(SearchTarget::PlayList) {
+            return self.on_search_prev();
+        }
         let len = self.play_list.len();
         change_list_index(len, &mut self.play_list_index, |i| i - 1, |len| len - 1);
     }
     // 添加到播放列表
     pub fn on_add_emdia_to_list(&mut self) {
+        // 有标记时添加所有标记的文件
+        if self.has_explorer_selection() {
+            return self.on_enqueue(EnqueueMode::Append);
+        }
         if let Some(selected) = self.list_index.selected() {
             let has_parent = self.has_parent();
 
@@ -216,6 +608,8 @@ impl Script {
                 if path.is_dir() {
                     self.current_dir = path;
                     self.list_index.select(Some(0));
+                    self.search.clear();
+                    self.load_dir();
                 }
                 // 添加到媒体文件
                 else if path.is_file() {
@@ -224,67 +618,870 @@ impl Script {
             }
         }
     }
+    // 选中的文件或文件夹 ; 不包括返回上一级
+    fn selected_path(&self) -> Option<PathBuf> {
+        let selected = self.list_index.selected()?;
+        let index = if self.has_parent() {
+            selected.checked_sub(1)?
+        } else {
+            selected
+        };
+        self.list.get(index).map(|it| it.path())
+    }
+    // 是否在文件夹列表的范围选择中 ; 索引包括返回上一级
+    pub fn in_explorer_range(&self, index: usize) -> bool {
+        Self::range(self.explorer_anchor, self.list_index.selected())
+            .is_some_and(|it| it.contains(&index))
+    }
+    // 标记文件夹列表中的文件 ; 切换文件夹后保留标记
+    pub fn on_mark_explorer(&mut self) {
+        if let Some(path) = self.selected_path() {
+            if !self.explorer_marked.remove(&path) {
+                self.explorer_marked.insert(path);
+            }
+        }
+    }
+    // 开始或结束范围选择 ; 结束时标记范围内的文件
+    pub fn on_explorer_range(&mut self) {
+        if self.explorer_anchor.is_some() {
+            self.commit_explorer_range();
+        } else {
+            self.explorer_anchor = self.list_index.selected();
+        }
+    }
+    fn commit_explorer_range(&mut self) {
+        let range = Self::range(self.explorer_anchor.take(), self.list_index.selected());
+        let offset = if self.has_parent() { 1 } else { 0 };
+        for i in range.into_iter().flatten() {
+            if let Some(it) = i.checked_sub(offset).and_then(|i| self.list.get(i)) {
+                self.explorer_marked.insert(it.path());
+            }
+        }
+    }
+    pub fn has_explorer_selection(&self) -> bool {
+        self.explorer_anchor.is_some() || !self.explorer_marked.is_empty()
+    }
+    pub fn on_clear_explorer_selection(&mut self) {
+        self.explorer_anchor = None;
+        self.explorer_marked.clear();
+    }
+    // 选中的文件和文件夹 ; 有标记时为所有标记的文件，否则为光标所在的文件
+    fn explorer_selection(&mut self) -> Vec<PathBuf> {
+        self.commit_explorer_range();
+        if self.explorer_marked.is_empty() {
+            return self.selected_path().into_iter().collect();
+        }
+        let mut paths: Vec<PathBuf> = self.explorer_marked.drain().collect();
+        paths.sort_by(|a, b| utils::natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
+        paths
+    }
+    // 添加选中的文件，或者递归添加选中文件夹中的所有媒体文件
+    pub fn on_enqueue(&mut self, mode: EnqueueMode) {
+        let paths = self.explorer_selection();
+        match paths.as_slice() {
+            [] => {}
+            [path] if path.is_file() => self.on_scanned(mode, paths),
+            _ => {
+                self.scan_progress = Some((0, 0));
+                let by_track = self.sort.mode == SortMode::Track;
+                enqueue::scan(paths, mode, by_track, self.events.clone());
+            }
+        }
+    }
+    pub fn on_scan_progress(&mut self, done: usize, total: usize) {
+        self.scan_progress = Some((done, total));
+    }
+    // 把找到的媒体文件添加到播放列表 ; 标签、歌词和时长在后台解析
+    pub fn on_scanned(&mut self, mode: EnqueueMode, files: Vec<PathBuf>) {
+        self.scan_progress = None;
+        if let EnqueueMode::Save(name) = mode {
+            return self.save_to_playlist(&name, files);
+        }
+        if let EnqueueMode::Smart(start) = mode {
+            return self.on_smart_evaluated(start, files);
+        }
+        let mut items = vec![];
+        for path in files {
+            if let Some(media) = Media::new_local_file_placeholder(path.clone()) {
+                self.loader.load(path);
+                items.push(PlayItem::new(media));
+            }
+        }
+        if items.is_empty() {
+            self.error = Some((Config::TIP_NO_MEDIA_FILES.to_string(), Instant::now()));
+            return;
+        }
+
+        // 下一首播放的歌曲不进入播放列表
+        if mode == EnqueueMode::PlayNext {
+            self.record_up_next();
+            self.up_next.extend(items);
+            self.update_watch();
+            if self.current.is_none() {
+                self.on_next();
+            }
+            return;
+        }
+        self.record_play_list();
+        if mode == EnqueueMode::Replace {
+            self.play_list.clear();
+            self.current = None;
+        }
+        let start = self.play_list.len();
+        self.play_list.extend(items);
+        self.play_list_changed();
+        if self.current.is_none() {
+            self.play_at(Some(start));
+        }
+    }
+    // 切换排序依据
+    pub fn on_next_sort_mode(&mut self) {
+        self.sort.next_mode();
+        self.reload_dir();
+    }
+    // 切换升序、降序
+    pub fn on_toggle_sort_order(&mut self) {
+        self.sort.toggle_order();
+        self.reload_dir();
+    }
+    // 显示或隐藏隐藏文件夹
+    pub fn on_toggle_hidden_dirs(&mut self) {
+        self.sort.toggle_hidden();
+        self.reload_dir();
+    }
     // 返回上一级菜单
     pub fn on_back_parent(&mut self) {
         if self.has_parent() {
             if let Some(d) = self.current_dir.parent() {
                 self.current_dir = d.to_path_buf();
                 self.list_index.select(Some(0));
+                self.search.clear();
+                self.load_dir();
             }
         }
     }
-    // 置顶到播放列表
-    pub fn on_post_top_play_list(&mut self) {
+    // 标记播放列表中的歌曲 ; 用于批量编辑标签
+    pub fn on_mark_play_list(&mut self) {
         if let Some(selected) = self.play_list_index.selected() {
-            if selected != 0 {
-                if let Some(elem) = self.play_list.get(selected).cloned() {
-                    self.play_list.remove(selected);
-                    self.play_list.insert(1, elem);
+            if let Some(it) = self.play_list.get_mut(selected) {
+                it.marked = !it.marked;
+            }
+        }
+    }
+    // 开始编辑标签 ; 有标记的歌曲时批量编辑，否则编辑正在播放的歌曲
+    pub fn on_tag_edit_start(&mut self) {
+        self.commit_play_list_range();
+        let mut targets: Vec<&PlayItem> = self.play_list.iter().filter(|it| it.marked).collect();
+        if targets.is_empty() {
+            targets.extend(self.current());
+        }
+        if let Some(first) = targets.first() {
+            let metadata = first.media.get_metadata();
+            let paths = targets
+                .iter()
+                .map(|it| it.media.get_path().clone())
+                .collect();
+            self.tag_editor = Some(TagEditor::new(paths, metadata));
+        }
+    }
+    pub fn on_tag_edit_up(&mut self) {
+        if let Some(editor) = &mut self.tag_editor {
+            editor.up();
+        }
+    }
+    pub fn on_tag_edit_down(&mut self) {
+        if let Some(editor) = &mut self.tag_editor {
+            editor.down();
+        }
+    }
+    // 开始或确认输入
+    pub fn on_tag_edit_enter(&mut self) {
+        if let Some(editor) = &mut self.tag_editor {
+            if editor.editing {
+                editor.confirm();
+            } else {
+                editor.start_edit();
+            }
+        }
+    }
+    pub fn on_tag_edit_input(&mut self, c: char) {
+        if let Some(editor) = &mut self.tag_editor {
+            editor.input.push(c);
+        }
+    }
+    pub fn on_tag_edit_backspace(&mut self) {
+        if let Some(editor) = &mut self.tag_editor {
+            editor.input.pop();
+        }
+    }
+    // 放弃输入，或退出编辑
+    pub fn on_tag_edit_cancel(&mut self) {
+        match &mut self.tag_editor {
+            Some(editor) if editor.editing => editor.cancel_edit(),
+            _ => self.tag_editor = None,
+        }
+    }
+    // 保存修改的标签 ; 媒体信息在后台重新读取
+    pub fn on_tag_edit_save(&mut self) {
+        let Some(editor) = self.tag_editor.take() else {
+            return;
+        };
+        let edits = editor.edits();
+        if edits.is_empty() {
+            return;
+        }
+        for path in editor.targets.iter() {
+            if let Err(e) = Metadata::write_to_path(path, &edits) {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                let message = format!("{} {}: {}", Config::TIP_TAG_SAVE_FAILED, name, e);
+                self.error = Some((message, Instant::now()));
+                continue;
+            }
+            self.search.forget(path);
+            self.sort.forget(path);
+            self.loader.load(path.clone());
+        }
+        for it in self.play_list.iter_mut() {
+            it.marked = false;
+        }
+        self.update_search();
+        self.schedule_refresh_smart(&editor.targets);
+    }
+    // 移动到正在播放的歌曲后面
+    pub fn on_post_top_play_list(&mut self) {
+        let current = self.current;
+        self.move_play_list_selection(|_, rest| match current {
+            Some(current) => rest.iter().take_while(|it| **it <= current).count(),
+            None => 0,
+        });
+    }
+    // 移动到末尾
+    pub fn on_move_play_list_to_end(&mut self) {
+        self.move_play_list_selection(|_, _| usize::MAX);
+    }
+    // 拖动到新的位置 ; 返回拖动后的位置
+    // 一次拖动只在第一次移动时记录撤销
+    pub fn on_drag_play_list(&mut self, from: usize, to: usize, record: bool) -> usize {
+        let len = self.play_list.len();
+        if from == to || from >= len || to >= len {
+            return from;
+        }
+        if record {
+            self.record_play_list();
+        }
+        self.reorder_play_list(Self::dragged_order(len, from, to));
+        self.play_list_index.select(Some(to));
+        to
+    }
+    // 拖动后的顺序
+    fn dragged_order(len: usize, from: usize, to: usize) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..len).collect();
+        let i = order.remove(from);
+        order.insert(to, i);
+        order
+    }
+    // 把选中的歌曲向上或向下移动 ; 负数向上
+    pub fn on_move_play_list(&mut self, offset: isize) {
+        self.move_play_list_selection(|first, _| first.saturating_add_signed(offset));
+    }
+    // 移动选中的歌曲 ; 移动后选中的歌曲连续排列
+    // position 根据第一首选中歌曲的位置和其余歌曲，返回插入位置
+    fn move_play_list_selection<F>(&mut self, position: F)
+    where
+        F: Fn(usize, &[usize]) -> usize,
+    {
+        let selection = self.play_list_selection();
+        if selection.is_empty() {
+            return;
+        }
+        self.record_play_list();
+        let (order, at) = Self::moved_order(self.play_list.len(), &selection, position);
+        self.reorder_play_list(order);
+        self.play_list_index.select(Some(at));
+    }
+    // 移动后的顺序和第一首选中歌曲的新位置 ; selection 不能为空
+    fn moved_order<F>(len: usize, selection: &[usize], position: F) -> (Vec<usize>, usize)
+    where
+        F: Fn(usize, &[usize]) -> usize,
+    {
+        let mut order: Vec<usize> = (0..len).filter(|it| !selection.contains(it)).collect();
+        let at = cmp::min(position(selection[0], &order), order.len());
+        order.splice(at..at, selection.iter().copied());
+        (order, at)
+    }
+    // 按新的顺序重排播放列表 ; order 为原来的位置，不在其中的歌曲被删除
+    fn reorder_play_list(&mut self, order: Vec<usize>) {
+        let mut items: Vec<Option<PlayItem>> = self.play_list.drain(..).map(Some).collect();
+        self.play_list = order.iter().filter_map(|i| items[*i].take()).collect();
+        self.current = Self::reordered_index(self.current, &order);
+        self.play_list_changed();
+    }
+    // 重排后原来位置的新位置
+    fn reordered_index(index: Option<usize>, order: &[usize]) -> Option<usize> {
+        index.and_then(|index| order.iter().position(|it| *it == index))
+    }
+    // 播放列表中的文件
+    fn play_list_paths(&self) -> Vec<PathBuf> {
+        self.play_list
+            .iter()
+            .map(|it| it.media.get_path().clone())
+            .collect()
+    }
+    // 播放列表和下一首播放中的文件
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            play_list: self.play_list_paths(),
+            up_next: self
+                .up_next
+                .iter()
+                .map(|it| it.media.get_path().clone())
+                .collect(),
+            current: self.current,
+        }
+    }
+    // 记录修改前的播放列表
+    // 手动修改后不再自动刷新智能播放列表
+    fn record_play_list(&mut self) {
+        self.record_up_next();
+        self.smart_source = None;
+    }
+    // 记录修改前的下一首播放 ; 不影响智能播放列表
+    fn record_up_next(&mut self) {
+        let state = self.snapshot();
+        self.history.record(state);
+    }
+    pub fn on_undo(&mut self) {
+        let current = self.snapshot();
+        if let Some(state) = self.history.undo(current) {
+            self.restore_snapshot(state);
+        }
+    }
+    pub fn on_redo(&mut self) {
+        let current = self.snapshot();
+        if let Some(state) = self.history.redo(current) {
+            self.restore_snapshot(state);
+        }
+    }
+    fn restore_snapshot(&mut self, state: Snapshot) {
+        let mut medias = self.parsed_medias();
+        self.up_next = state
+            .up_next
+            .into_iter()
+            .filter_map(|path| self.reuse_media(&mut medias, path))
+            .map(PlayItem::new)
+            .collect();
+        self.restore_play_list(state.play_list, state.current);
+    }
+    // 已经解析的媒体信息
+    fn parsed_medias(&self) -> HashMap<PathBuf, Media> {
+        self.play_list
+            .iter()
+            .chain(self.up_next.iter())
+            .map(|it| (it.media.get_path().clone(), it.media.clone()))
+            .collect()
+    }
+    // 复用已经解析的媒体信息 ; 没有时在后台解析
+    fn reuse_media(&self, medias: &mut HashMap<PathBuf, Media>, path: PathBuf) -> Option<Media> {
+        if let Some(media) = medias.get(&path) {
+            return Some(media.clone());
+        }
+        let media = Media::new_local_file_placeholder(path.clone())?;
+        self.loader.load(path);
+        medias.insert(media.get_path().clone(), media.clone());
+        Some(media)
+    }
+    // 恢复播放列表 ; 复用已经解析的媒体信息，正在播放的歌曲还在列表中时继续播放
+    // index 为正在播放的歌曲在新列表中的位置 ; 同一首歌曲出现多次时用来区分
+    fn restore_play_list(&mut self, paths: Vec<PathBuf>, index: Option<usize>) {
+        let current = self.current().cloned();
+        self.current = None;
+        let mut medias = self.parsed_medias();
+        self.play_list.clear();
+        for path in paths {
+            if let Some(media) = self.reuse_media(&mut medias, path) {
+                self.play_list.push(PlayItem::new(media));
+            }
+        }
+
+        if let Some(current) = current {
+            let paths = self.play_list_paths();
+            match Self::restored_index(&paths, current.media.get_path(), index) {
+                Some(i) => {
+                    self.play_list[i] = current;
+                    self.current = Some(i);
                 }
+                None => self.player.clear(),
             }
         }
+        self.play_list_changed();
+        let last = self.play_list.len().saturating_sub(1);
+        let selected = self.play_list_index.selected().unwrap_or(0);
+        self.play_list_index.select(Some(cmp::min(selected, last)));
+    }
+    // 正在播放的歌曲在恢复后的位置 ; 记录的位置不是这首歌曲时按路径查找
+    fn restored_index(paths: &[PathBuf], path: &PathBuf, index: Option<usize>) -> Option<usize> {
+        index
+            .filter(|i| paths.get(*i) == Some(path))
+            .or_else(|| paths.iter().position(|it| it == path))
+    }
+    // 是否在播放列表的范围选择中
+    pub fn in_play_list_range(&self, index: usize) -> bool {
+        Self::range(self.play_list_anchor, self.play_list_index.selected())
+            .is_some_and(|it| it.contains(&index))
+    }
+    // 开始或结束范围选择 ; 结束时标记范围内的歌曲
+    pub fn on_play_list_range(&mut self) {
+        if self.play_list_anchor.is_some() {
+            self.commit_play_list_range();
+        } else {
+            self.play_list_anchor = self.play_list_index.selected();
+        }
+    }
+    fn commit_play_list_range(&mut self) {
+        if let Some(range) = Self::range(
+            self.play_list_anchor.take(),
+            self.play_list_index.selected(),
+        ) {
+            for (i, it) in self.play_list.iter_mut().enumerate() {
+                if range.contains(&i) {
+                    it.marked = true;
+                }
+            }
+        }
+    }
+    pub fn has_play_list_selection(&self) -> bool {
+        self.play_list_anchor.is_some() || self.play_list.iter().any(|it| it.marked)
+    }
+    pub fn on_clear_play_list_selection(&mut self) {
+        self.play_list_anchor = None;
+        for it in self.play_list.iter_mut() {
+            it.marked = false;
+        }
+    }
+    // 选中的歌曲 ; 有标记时为所有标记的歌曲，否则为光标所在的歌曲
+    fn play_list_selection(&mut self) -> Vec<usize> {
+        self.commit_play_list_range();
+        let marked: Vec<usize> = self
+            .play_list
+            .iter()
+            .enumerate()
+            .filter(|(_, it)| it.marked)
+            .map(|(i, _)| i)
+            .collect();
+        if !marked.is_empty() {
+            return marked;
+        }
+        self.play_list_index
+            .selected()
+            .filter(|it| *it < self.play_list.len())
+            .into_iter()
+            .collect()
+    }
+    // 只保留选中的歌曲 ; 正在播放的歌曲没有选中时，播放第一首选中的歌曲
+    pub fn on_crop_play_list(&mut self) {
+        let selection = self.play_list_selection();
+        if selection.is_empty() {
+            return;
+        }
+        self.record_play_list();
+        self.reorder_play_list(selection);
+        self.on_clear_play_list_selection();
+        self.play_list_index.select(Some(0));
+        if self.current.is_none() {
+            self.play_at(Some(0));
+        }
+    }
+    // 把选中的歌曲添加到保存的播放列表 ; 先选择播放列表
+    pub fn on_save_play_list_selection(&mut self) {
+        let files: Vec<PathBuf> = self
+            .play_list_selection()
+            .into_iter()
+            .filter_map(|i| self.play_list.get(i))
+            .map(|it| it.media.get_path().clone())
+            .collect();
+        self.on_clear_play_list_selection();
+        self.open_playlist_picker(files);
+    }
+    // 把选中的文件添加到保存的播放列表 ; 先选择播放列表
+    pub fn on_save_explorer_selection(&mut self) {
+        let paths = self.explorer_selection();
+        self.open_playlist_picker(paths);
+    }
+    fn open_playlist_picker(&mut self, paths: Vec<PathBuf>) {
+        if paths.is_empty() {
+            return;
+        }
+        self.playlists.reload(&self.active_playlist);
+        let mut index = ListState::default();
+        let active = self
+            .playlists
+            .names
+            .iter()
+            .position(|it| *it == self.active_playlist);
+        index.select(Some(active.unwrap_or(0)));
+        self.playlists.picker = Some((index, paths));
     }
-    // 插队播放当前歌曲
+    pub fn on_picker_up(&mut self) {
+        self.playlists.picker_up();
+    }
+    pub fn on_picker_down(&mut self) {
+        self.playlists.picker_down();
+    }
+    pub fn on_picker_cancel(&mut self) {
+        self.playlists.picker = None;
+    }
+    // 添加到选择的播放列表 ; 有文件夹时在后台查找
+    pub fn on_picker_confirm(&mut self) {
+        let Some(name) = self.playlists.picked().cloned() else {
+            return;
+        };
+        let Some((_, paths)) = self.playlists.picker.take() else {
+            return;
+        };
+        if paths.iter().all(|it| it.is_file()) {
+            self.save_to_playlist(&name, paths);
+        } else {
+            self.scan_progress = Some((0, 0));
+            enqueue::scan(paths, EnqueueMode::Save(name), false, self.events.clone());
+        }
+    }
+    // 打开播放列表管理 ; 选中正在使用的播放列表
+    pub fn on_playlists_open(&mut self) {
+        self.playlists.reload(&self.active_playlist);
+        self.playlists.select_name(&self.active_playlist);
+    }
+    pub fn on_playlists_up(&mut self) {
+        self.playlists.up();
+    }
+    pub fn on_playlists_down(&mut self) {
+        self.playlists.down();
+    }
+    // 切换正在使用的播放列表 ; 先保存当前的播放列表
+    pub fn on_switch_playlist(&mut self) {
+        if let Some(name) = self.playlists.selected_smart().cloned() {
+            return self.on_load_smart(name);
+        }
+        let Some(name) = self.playlists.selected().cloned() else {
+            return;
+        };
+        if name == self.active_playlist {
+            return;
+        }
+        if let Err(e) = self.save_active_playlist() {
+            let message = format!("{}: {}", Config::TIP_PLAYLIST_SAVE_FAILED, e);
+            self.error = Some((message, Instant::now()));
+            return;
+        }
+        self.active_playlist = name;
+        let _ = playlist::set_active_name(&self.active_playlist);
+        self.load_active_playlist();
+    }
+    // 开始输入播放列表名称 ; 重命名和复制时以选中的名称开始
+    pub fn on_playlist_input_start(&mut self, kind: PlaylistInput) {
+        let name = match kind {
+            PlaylistInput::Create | PlaylistInput::Smart => String::default(),
+            PlaylistInput::Query => {
+                let Some(name) = self.playlists.selected_smart().cloned() else {
+                    return;
+                };
+                let (_, query) = playlist::read_smart(&name).unwrap_or_default();
+                self.playlists.editing_smart = Some(name);
+                query
+            }
+            _ => match self.playlists.selected() {
+                Some(name) => name.clone(),
+                None => return,
+            },
+        };
+        self.playlists.input = Some((kind, name));
+    }
+    pub fn on_playlist_input(&mut self, c: char) {
+        if let Some((_, input)) = &mut self.playlists.input {
+            input.push(c);
+        }
+    }
+    pub fn on_playlist_input_backspace(&mut self) {
+        if let Some((_, input)) = &mut self.playlists.input {
+            input.pop();
+        }
+    }
+    pub fn on_playlist_input_cancel(&mut self) {
+        self.playlists.input = None;
+        self.playlists.editing_smart = None;
+    }
+    // 新建、重命名或复制播放列表
+    // 正在使用的播放列表先保存，重命名后继续使用新的名称
+    pub fn on_playlist_input_confirm(&mut self) {
+        let Some((kind, input)) = self.playlists.input.take() else {
+            return;
+        };
+        let name = input.trim().to_string();
+        match kind {
+            PlaylistInput::Smart => return self.on_smart_name_confirm(name),
+            PlaylistInput::Query => return self.on_smart_query_confirm(name),
+            _ => {}
+        }
+        let selected = self.playlists.selected().cloned().unwrap_or_default();
+        let result = self.save_active_playlist().and_then(|_| match kind {
+            PlaylistInput::Create => playlist::create(&name),
+            PlaylistInput::Rename => playlist::rename(&selected, &name),
+            PlaylistInput::Duplicate => playlist::duplicate(&selected, &name),
+            PlaylistInput::Smart | PlaylistInput::Query => Ok(()),
+        });
+        match result {
+            Ok(()) => {
+                if kind == PlaylistInput::Rename && selected == self.active_playlist {
+                    self.active_playlist = name.clone();
+                    let _ = playlist::set_active_name(&name);
+                }
+                self.playlists.reload(&self.active_playlist);
+                self.playlists.select_name(&name);
+            }
+            Err(e) => {
+                let message = format!("{} {}: {}", Config::TIP_PLAYLIST_OPERATION_FAILED, name, e);
+                self.error = Some((message, Instant::now()));
+            }
+        }
+    }
+    // 新建智能播放列表 ; 名称可用时继续输入查询
+    fn on_smart_name_confirm(&mut self, name: String) {
+        if let Err(e) = playlist::check_new_name(&name) {
+            let message = format!("{} {}: {}", Config::TIP_PLAYLIST_OPERATION_FAILED, name, e);
+            self.error = Some((message, Instant::now()));
+            return;
+        }
+        self.playlists.editing_smart = Some(name);
+        self.playlists.input = Some((PlaylistInput::Query, String::default()));
+    }
+    // 保存智能播放列表的查询 ; 查询无效时继续编辑
+    // 新建的智能播放列表在文件夹列表的当前目录中查找
+    fn on_smart_query_confirm(&mut self, query: String) {
+        let Some(name) = self.playlists.editing_smart.clone() else {
+            return;
+        };
+        if let Err(token) = Query::parse(&query) {
+            let message = format!("{}: {}", Config::TIP_QUERY_INVALID, token);
+            self.error = Some((message, Instant::now()));
+            self.playlists.input = Some((PlaylistInput::Query, query));
+            return;
+        }
+        let root = match playlist::read_smart(&name) {
+            Ok((root, _)) => root,
+            Err(_) => self.current_dir.clone(),
+        };
+        self.playlists.editing_smart = None;
+        if let Err(e) = playlist::write_smart(&name, &root, &query) {
+            let message = format!("{} {}: {}", Config::TIP_PLAYLIST_OPERATION_FAILED, name, e);
+            self.error = Some((message, Instant::now()));
+            return;
+        }
+        self.playlists.reload(&self.active_playlist);
+        self.playlists.select_smart(&name);
+        if self
+            .smart_source
+            .as_ref()
+            .is_some_and(|(it, _)| *it == name)
+        {
+            self.refresh_smart();
+        }
+    }
+    // 读取智能播放列表的查询
+    fn read_smart(&mut self, name: &str) -> Option<(PathBuf, Query)> {
+        let result = playlist::read_smart(name)
+            .map_err(|e| e.to_string())
+            .and_then(|(root, query)| Ok((root, Query::parse(&query)?)));
+        match result {
+            Ok(it) => Some(it),
+            Err(e) => {
+                let message = format!("{} {}: {}", Config::TIP_QUERY_INVALID, name, e);
+                self.error = Some((message, Instant::now()));
+                None
+            }
+        }
+    }
+    // 把智能播放列表的结果加载到播放列表 ; 在后台查找
+    pub fn on_load_smart(&mut self, name: String) {
+        let Some((root, query)) = self.read_smart(&name) else {
+            return;
+        };
+        self.scan_progress = Some((0, 0));
+        self.smart_source = Some((name, root.clone()));
+        self.update_watch();
+        let stats = self.stats.snapshot();
+        let events = self.events.clone();
+        enqueue::evaluate(root, query, stats, EnqueueMode::Smart(true), events);
+    }
+    // 重新查找正在使用的智能播放列表 ; 文件或标签变化后调用
+    // 随机抽样的智能播放列表不自动刷新，避免打乱播放顺序
+    fn refresh_smart(&mut self) {
+        let Some((name, _)) = self.smart_source.clone() else {
+            return;
+        };
+        if let Some((root, query)) = self.read_smart(&name) {
+            if !query.random {
+                let stats = self.stats.snapshot();
+                let events = self.events.clone();
+                enqueue::evaluate(root, query, stats, EnqueueMode::Smart(false), events);
+            }
+        }
+    }
+    // 查找的文件夹中有文件变化时，等待一段时间后刷新 ; 连续的变化只刷新一次
+    fn schedule_refresh_smart(&mut self, paths: &[PathBuf]) {
+        let Some((_, root)) = &self.smart_source else {
+            return;
+        };
+        if paths.iter().any(|it| it.starts_with(root)) {
+            self.smart_refresh_at = Some(Instant::now() + Config::SMART_REFRESH_DELAY);
+        }
+    }
+    // 智能播放列表的结果替换播放列表 ; 正在播放的歌曲还在结果中时继续播放
+    fn on_smart_evaluated(&mut self, start: bool, files: Vec<PathBuf>) {
+        // 查找期间手动修改了播放列表
+        if self.smart_source.is_none() {
+            return;
+        }
+        if files.is_empty() && start {
+            self.error = Some((Config::TIP_NO_MEDIA_FILES.to_string(), Instant::now()));
+            return;
+        }
+        if files == self.play_list_paths() {
+            return;
+        }
+        self.record_up_next();
+        self.restore_play_list(files, self.current);
+        if start {
+            self.play_list_index.select(Some(0));
+            if self.current.is_none() {
+                self.play_at(Some(0));
+            }
+        }
+    }
+    // 删除选中的播放列表 ; 不能删除正在使用的播放列表
+    pub fn on_delete_playlist(&mut self) {
+        if let Some(name) = self.playlists.selected_smart().cloned() {
+            if let Err(e) = playlist::remove_smart(&name) {
+                let message = format!("{} {}: {}", Config::TIP_PLAYLIST_OPERATION_FAILED, name, e);
+                self.error = Some((message, Instant::now()));
+            }
+            return self.playlists.reload(&self.active_playlist);
+        }
+        let Some(name) = self.playlists.selected().cloned() else {
+            return;
+        };
+        if name == self.active_playlist {
+            self.error = Some((Config::TIP_PLAYLIST_IS_ACTIVE.to_string(), Instant::now()));
+            return;
+        }
+        if let Err(e) = playlist::remove(&name) {
+            let message = format!("{} {}: {}", Config::TIP_PLAYLIST_OPERATION_FAILED, name, e);
+            self.error = Some((message, Instant::now()));
+        }
+        self.playlists.reload(&self.active_playlist);
+    }
+    // 立即播放选中的歌曲
     pub fn on_play_now_play_list(&mut self) {
         if let Some(selected) = self.play_list_index.selected() {
-            if selected != 0 {
-                if let Some(elem) = self.play_list.get(selected).cloned() {
-                    self.play_list.remove(selected);
-                    self.play_list.remove(0);
-                    self.play_list.insert(0, elem);
-                    self.play();
-                    self.play_list_index.select(Some(0));
-                }
+            if selected < self.play_list.len() {
+                self.play_at(Some(selected));
             }
         }
     }
-    // 从播放列表删除
+    // 把选中的歌曲添加到下一首播放
+    pub fn on_play_next_play_list(&mut self) {
+        let items: Vec<PlayItem> = self
+            .play_list_selection()
+            .into_iter()
+            .filter_map(|i| self.play_list.get(i))
+            .map(|it| PlayItem::new(it.media.clone()))
+            .collect();
+        self.on_clear_play_list_selection();
+        self.record_up_next();
+        self.up_next.extend(items);
+    }
+    // 清空下一首播放
+    pub fn on_clear_up_next(&mut self) {
+        self.record_up_next();
+        self.up_next.clear();
+        self.update_watch();
+    }
+    // 从播放列表删除 ; 删除正在播放的歌曲时播放后面的一首
     pub fn on_remove_from_play_list(&mut self) {
-        if let Some(selected) = self.play_list_index.selected() {
-            if selected == 0 {
+        let selection = self.play_list_selection();
+        let Some(&first) = selection.first() else {
+            return;
+        };
+        self.record_play_list();
+        let removed_current = self.current.is_some_and(|it| selection.contains(&it));
+        let next = self
+            .current
+            .map(|current| (0..current).filter(|it| !selection.contains(it)).count());
+        let order = (0..self.play_list.len())
+            .filter(|it| !selection.contains(it))
+            .collect();
+        self.reorder_play_list(order);
+        if removed_current {
+            self.play_at(next);
+            if self.current.is_none() {
                 self.on_next();
-            } else if selected < self.play_list.len() {
-                self.play_list.remove(selected);
             }
         }
+        let last = self.play_list.len().saturating_sub(1);
+        self.play_list_index.select(Some(cmp::min(first, last)));
     }
-    // 播放下一首
+    // 播放完毕，下一首 ; 忽略已经切换的歌曲的结束事件
+    pub fn on_track_end(&mut self, generation: usize) {
+        if !self.player.is_current(generation) {
+            return;
+        }
+        if self.current().is_some_and(|it| it.is_playing()) {
+            self.count_play();
+            if let Some(it) = self.current.and_then(|i| self.play_list.get_mut(i)) {
+                it.finish();
+            }
+            self.on_next();
+        }
+    }
+    // 填充后台解析完成的媒体信息
+    pub fn on_media_loaded(&mut self, media: Media) {
+        if self.sort.loaded(&media) {
+            self.reload_dir();
+        }
+        for it in self.play_list.iter_mut().chain(self.up_next.iter_mut()) {
+            if it.media.get_path() == media.get_path() {
+                it.media = media.clone();
+            }
+        }
+        // 标签和歌词解析完成后更新搜索结果 ; 文件夹列表等待的标签全部解析完成后才更新
+        let tags_loaded = self.search.loaded(&media);
+        if tags_loaded || !self.search.is_active(SearchTarget::Explorer) {
+            self.update_search();
+        }
+    }
+    // 播放下一首 ; 先播放下一首播放中的歌曲，插入到正在播放的歌曲后面
+    // 播放列表播放完毕后停止，此时只播放新添加的歌曲
     pub fn on_next(&mut self) {
-        if self.play_list.len() > 0 {
-            self.play_list.remove(0);
-            self.play();
+        let next = self.current.map_or(self.play_list.len(), |it| it + 1);
+        if let Some(item) = self.up_next.pop_front() {
+            self.play_list.insert(next, item);
+            self.play_list_changed();
+            if let Some(selected) = self.play_list_index.selected().filter(|it| *it >= next) {
+                self.play_list_index.select(Some(selected + 1));
+            }
+            self.play_at(Some(next));
+        } else if self.current.is_some() {
+            self.play_at(Some(next));
         }
     }
     // 暂停当前播放
     pub fn on_pause(&mut self) {
-        if let Some(first) = self.play_list.first_mut() {
+        if let Some(first) = self.current.and_then(|i| self.play_list.get_mut(i)) {
             first.pause();
             self.player.pause();
         }
     }
     // 恢复播放
     pub fn on_play(&mut self) {
-        if let Some(first) = self.play_list.first_mut() {
+        if let Some(first) = self.current.and_then(|i| self.play_list.get_mut(i)) {
             // 前进后退的时候，将不挂载资源
             if self.player.is_empty() {
                 let (d, _) = first.progress();
@@ -297,7 +1494,7 @@ impl Script {
     }
     // 切换暂停 or 播放
     pub fn on_change_pause_play(&mut self) {
-        if let Some(first) = self.play_list.first_mut() {
+        if let Some(first) = self.current.and_then(|i| self.play_list.get_mut(i)) {
             if first.is_playing() {
                 self.on_pause();
             } else {
@@ -305,18 +1502,315 @@ impl Script {
             }
         }
     }
+    // 调整当前歌词的偏移 ; 毫秒，正数表示歌词提前
+    pub fn on_lyric_offset(&mut self, delta: i64) {
+        if let Some(first) = self.current.and_then(|i| self.play_list.get_mut(i)) {
+            first.media.get_lyrics_mut().shift(delta);
+        }
+    }
+    // 保存歌词偏移到歌词文件
+    pub fn on_save_lyric_offset(&mut self) {
+        let Some(first) = self.current.and_then(|i| self.play_list.get_mut(i)) else {
+            return;
+        };
+        let lyrics = first.media.get_lyrics();
+        if lyrics.adjust == 0 {
+            return;
+        }
+        match lyrics.save_offset() {
+            Ok(_) => first.media.reload_lyrics(),
+            Err(e) => {
+                let message = format!("{}: {}", Config::TIP_LYRIC_OFFSET_SAVE_FAILED, e);
+                self.error = Some((message, Instant::now()));
+            }
+        }
+    }
+    // 开始同步纯文本歌词
+    pub fn on_lyric_sync_start(&mut self) {
+        if let Some(first) = self.current() {
+            let lyrics = first.media.get_lyrics();
+            if lyrics.list.is_empty() && !lyrics.unsynced.is_empty() {
+                self.lyric_sync = Some(LyricSync::new(
+                    first.media.get_path().clone(),
+                    lyrics.unsynced.clone(),
+                ));
+            }
+        }
+    }
+    // 标记当前行 ; 只在播放正在同步的歌曲时标记
+    pub fn on_lyric_sync_tap(&mut self) {
+        if let (Some(sync), Some(first)) = (
+            &mut self.lyric_sync,
+            self.current.and_then(|i| self.play_list.get(i)),
+        ) {
+            if *first.media.get_path() == sync.path {
+                let (position, _) = first.progress();
+                sync.tap(position);
+            }
+        }
+    }
+    pub fn on_lyric_sync_up(&mut self) {
+        if let Some(sync) = &mut self.lyric_sync {
+            sync.up();
+        }
+    }
+    pub fn on_lyric_sync_down(&mut self) {
+        if let Some(sync) = &mut self.lyric_sync {
+            sync.down();
+        }
+    }
+    // 微调当前行的时间
+    pub fn on_lyric_sync_nudge(&mut self, delta: i64) {
+        if let Some(sync) = &mut self.lyric_sync {
+            sync.nudge(delta);
+        }
+    }
+    // 保存为同步的歌曲的同名lrc文件 ; 切换歌曲后也保存到原来的歌曲
+    pub fn on_lyric_sync_save(&mut self) {
+        let Some(sync) = &self.lyric_sync else {
+            return;
+        };
+        let source = sync.path.with_extension("lrc");
+        match std::fs::write(&source, sync.to_lrc()) {
+            Ok(_) => {
+                let path = sync.path.clone();
+                self.lyric_sync = None;
+                for it in self.play_list.iter_mut().chain(self.up_next.iter_mut()) {
+                    if *it.media.get_path() == path {
+                        it.media.get_lyrics_mut().source = Some(source.clone());
+                        it.media.reload_lyrics();
+                    }
+                }
+                if self.search.is_active(SearchTarget::Lyrics) {
+                    self.update_search();
+                }
+            }
+            Err(e) => {
+                let message = format!("{}: {}", Config::TIP_LYRIC_SAVE_FAILED, e);
+                self.error = Some((message, Instant::now()));
+            }
+        }
+    }
+    // 退出同步模式 ; 不保存
+    pub fn on_lyric_sync_cancel(&mut self) {
+        self.lyric_sync = None;
+    }
+    // 歌词光标所在的行
+    pub fn lyric_cursor(&self) -> Option<usize> {
+        self.lyric_cursor.map(|(index, _)| index)
+    }
+    // 切换歌词面板焦点
+    pub fn on_lyric_focus(&mut self) {
+        self.lyric_focus = !self.lyric_focus;
+        self.lyric_cursor = None;
+        if self.search.target == SearchTarget::Lyrics {
+            self.search.clear();
+        }
+    }
+    // 移动歌词光标 ; 没有光标时从正在唱的一行开始
+    fn move_lyric_cursor(&mut self, up: bool) {
+        let Some(first) = self.current() else {
+            return;
+        };
+        let lyrics = first.media.get_lyrics();
+        let len = lyrics.list.len();
+        if len == 0 {
+            return;
+        }
+        let index = match self.lyric_cursor() {
+            Some(index) if up => index.saturating_sub(1),
+            Some(index) => cmp::min(index + 1, len - 1),
+            None => {
+                let (position, _) = first.progress();
+                lyrics.current_index(lyrics.position(position)).unwrap_or(0)
+            }
+        };
+        self.lyric_cursor = Some((index, Instant::now()));
+    }
+    pub fn on_lyric_up(&mut self) {
+        self.move_lyric_cursor(true);
+    }
+    pub fn on_lyric_down(&mut self) {
+        self.move_lyric_cursor(false);
+    }
+    // 跳转到光标所在的歌词
+    pub fn on_lyric_seek(&mut self) {
+        let (Some(index), Some(first)) = (
+            self.lyric_cursor(),
+            self.current.and_then(|i| self.play_list.get_mut(i)),
+        ) else {
+            return;
+        };
+        let lyrics = first.media.get_lyrics();
+        let Some(lyric) = lyrics.list.get(index) else {
+            return;
+        };
+        let position = lyrics.playback_position(lyric.time);
+        let is_playing = first.is_playing();
+        first.seek(position);
+        self.player.clear();
+        self.lyric_cursor = None;
+        if is_playing {
+            self.play_offset(position);
+        }
+    }
+    // 切换歌词展示方式 ; 原文和翻译 -> 原文 -> 翻译
+    pub fn on_change_lyric_mode(&mut self) {
+        self.lyric_mode = match self.lyric_mode {
+            LyricMode::Both => LyricMode::Original,
+            LyricMode::Original => LyricMode::Translation,
+            LyricMode::Translation => LyricMode::Both,
+        };
+    }
     // 前进 ; 前进后退工程中，不挂载媒体资源，恢复播放的时候才挂载
     pub fn on_forward(&mut self) {
-        if let Some(first) = self.play_list.first_mut() {
+        if let Some(first) = self.current.and_then(|i| self.play_list.get_mut(i)) {
             first.forward();
             self.player.clear();
         }
     }
     // 后退
     pub fn on_reverse(&mut self) {
-        if let Some(first) = self.play_list.first_mut() {
+        if let Some(first) = self.current.and_then(|i| self.play_list.get_mut(i)) {
             first.reverse();
             self.player.clear();
         }
     }
+    // 开始搜索
+    pub fn on_search_start(&mut self, target: SearchTarget) {
+        self.search.start(target);
+        self.update_search();
+    }
+    // 输入搜索内容
+    pub fn on_search_input(&mut self, c: char) {
+        self.search.input.push(c);
+        self.search.index.select(Some(0));
+        self.update_search();
+    }
+    // 删除搜索内容
+    pub fn on_search_backspace(&mut self) {
+        self.search.input.pop();
+        self.search.index.select(Some(0));
+        self.update_search();
+    }
+    // 确认搜索 ; 保留过滤结果，可以使用n/N跳转
+    pub fn on_search_confirm(&mut self) {
+        self.search.editing = false;
+        if self.search.input.is_empty() {
+            self.search.clear();
+        }
+    }
+    // 取消搜索 ; 原列表保持选中当前匹配项
+    pub fn on_search_cancel(&mut self) {
+        self.sync_search_selected();
+        self.search.clear();
+    }
+    // 下一个匹配项
+    pub fn on_search_next(&mut self) {
+        self.search.next();
+        self.sync_search_selected();
+    }
+    // 上一个匹配项
+    pub fn on_search_prev(&mut self) {
+        self.search.prev();
+        self.sync_search_selected();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn move_by(len: usize, selection: &[usize], offset: isize) -> (Vec<usize>, usize) {
+        Script::moved_order(len, selection, |first, _| {
+            first.saturating_add_signed(offset)
+        })
+    }
+
+    #[test]
+    fn first_item_moved_up_stays_first() {
+        let (order, at) = move_by(4, &[0], -1);
+        assert_eq!(order, vec![0, 1, 2, 3]);
+        assert_eq!(at, 0);
+        let (order, at) = move_by(4, &[0], -3);
+        assert_eq!(order, vec![0, 1, 2, 3]);
+        assert_eq!(at, 0);
+    }
+
+    #[test]
+    fn last_item_moved_down_stays_last() {
+        let (order, at) = move_by(4, &[3], 1);
+        assert_eq!(order, vec![0, 1, 2, 3]);
+        assert_eq!(at, 3);
+        let (order, at) = move_by(4, &[3], isize::MAX);
+        assert_eq!(order, vec![0, 1, 2, 3]);
+        assert_eq!(at, 3);
+    }
+
+    #[test]
+    fn move_keeps_current_on_the_same_item() {
+        // 正在播放第三首，把第一首向下移动两位
+        let (order, at) = move_by(4, &[0], 2);
+        assert_eq!(order, vec![1, 2, 0, 3]);
+        assert_eq!(at, 2);
+        assert_eq!(Script::reordered_index(Some(2), &order), Some(1));
+        assert_eq!(Script::reordered_index(Some(0), &order), Some(2));
+        assert_eq!(Script::reordered_index(None, &order), None);
+    }
+
+    #[test]
+    fn move_several_items_together() {
+        // 不连续的选中歌曲移动后连续排列
+        let (order, at) = move_by(6, &[1, 4], 1);
+        assert_eq!(order, vec![0, 2, 1, 4, 3, 5]);
+        assert_eq!(at, 2);
+        assert_eq!(Script::reordered_index(Some(4), &order), Some(3));
+        assert_eq!(Script::reordered_index(Some(3), &order), Some(4));
+    }
+
+    #[test]
+    fn move_after_current() {
+        let current = 2;
+        let (order, at) = Script::moved_order(5, &[4], |_, rest| {
+            rest.iter().take_while(|it| **it <= current).count()
+        });
+        assert_eq!(order, vec![0, 1, 2, 4, 3]);
+        assert_eq!(at, 3);
+        assert_eq!(Script::reordered_index(Some(current), &order), Some(2));
+    }
+
+    #[test]
+    fn drag_moves_one_item() {
+        let order = Script::dragged_order(4, 0, 3);
+        assert_eq!(order, vec![1, 2, 3, 0]);
+        assert_eq!(Script::reordered_index(Some(0), &order), Some(3));
+        assert_eq!(Script::reordered_index(Some(3), &order), Some(2));
+
+        let order = Script::dragged_order(4, 3, 0);
+        assert_eq!(order, vec![3, 0, 1, 2]);
+        assert_eq!(Script::reordered_index(Some(3), &order), Some(0));
+        assert_eq!(Script::reordered_index(Some(1), &order), Some(2));
+    }
+
+    #[test]
+    fn reorder_drops_missing_current() {
+        assert_eq!(Script::reordered_index(Some(1), &[0, 2]), None);
+    }
+
+    #[test]
+    fn restore_keeps_current_on_the_same_copy() {
+        let paths: Vec<PathBuf> = ["a", "b", "a"].iter().map(PathBuf::from).collect();
+        let a = PathBuf::from("a");
+        // 同一首歌曲出现两次，正在播放第二次
+        assert_eq!(Script::restored_index(&paths, &a, Some(2)), Some(2));
+        assert_eq!(Script::restored_index(&paths, &a, Some(0)), Some(0));
+        // 记录的位置已经不是这首歌曲时按路径查找
+        assert_eq!(Script::restored_index(&paths, &a, Some(1)), Some(0));
+        assert_eq!(Script::restored_index(&paths, &a, Some(9)), Some(0));
+        assert_eq!(Script::restored_index(&paths, &a, None), Some(0));
+        assert_eq!(
+            Script::restored_index(&paths, &PathBuf::from("c"), Some(0)),
+            None
+        );
+    }
 }
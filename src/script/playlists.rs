@@ -0,0 +1,115 @@
+use std::path::PathBuf;
+
+use ratatui::widgets::ListState;
+
+use crate::media::playlist;
+
+// 播放列表名称的输入用途
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum PlaylistInput {
+    Create,    // 新建
+    Rename,    // 重命名
+    Duplicate, // 复制
+    Smart,     // 新建智能播放列表 ; 输入名称后输入查询
+    Query,     // 智能播放列表的查询
+}
+
+// 播放列表管理
+pub struct Playlists {
+    pub names: Vec<String>,                        // 保存的播放列表
+    pub smart: Vec<String>,                        // 智能播放列表 ; 展示在保存的播放列表后面
+    pub index: ListState,                          // 列表状态
+    pub input: Option<(PlaylistInput, String)>,    // 正在输入的名称或查询
+    pub editing_smart: Option<String>,             // 正在输入查询的智能播放列表
+    pub picker: Option<(ListState, Vec<PathBuf>)>, // 选择要添加到的播放列表 ; 要添加的文件和文件夹
+}
+
+impl Playlists {
+    pub fn new() -> Self {
+        let mut index = ListState::default();
+        index.select(Some(0));
+        Self {
+            names: vec![],
+            smart: vec![],
+            index,
+            input: None,
+            editing_smart: None,
+            picker: None,
+        }
+    }
+
+    // 重新读取播放列表 ; 正在使用的播放列表没有保存过时也展示
+    pub fn reload(&mut self, active: &str) {
+        self.names = playlist::names();
+        if !self.names.iter().any(|it| it == active) {
+            self.names.insert(0, active.to_string());
+        }
+        self.smart = playlist::smart_names();
+        let last = self.len().saturating_sub(1);
+        let selected = self.index.selected().unwrap_or(0);
+        self.index.select(Some(selected.min(last)));
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len() + self.smart.len()
+    }
+
+    // 选中的播放列表
+    pub fn selected(&self) -> Option<&String> {
+        self.names.get(self.index.selected()?)
+    }
+
+    // 选中的智能播放列表
+    pub fn selected_smart(&self) -> Option<&String> {
+        let i = self.index.selected()?.checked_sub(self.names.len())?;
+        self.smart.get(i)
+    }
+
+    // 选中名称对应的位置
+    pub fn select_name(&mut self, name: &str) {
+        if let Some(i) = self.names.iter().position(|it| it == name) {
+            self.index.select(Some(i));
+        }
+    }
+
+    // 选中智能播放列表
+    pub fn select_smart(&mut self, name: &str) {
+        if let Some(i) = self.smart.iter().position(|it| it == name) {
+            self.index.select(Some(self.names.len() + i));
+        }
+    }
+
+    pub fn up(&mut self) {
+        let selected = self.index.selected().unwrap_or(0);
+        self.index.select(Some(selected.saturating_sub(1)));
+    }
+
+    pub fn down(&mut self) {
+        let selected = self.index.selected().unwrap_or(0);
+        if selected + 1 < self.len() {
+            self.index.select(Some(selected + 1));
+        }
+    }
+
+    // 选择器中选中的播放列表
+    pub fn picked(&self) -> Option<&String> {
+        let (index, _) = self.picker.as_ref()?;
+        self.names.get(index.selected()?)
+    }
+
+    pub fn picker_up(&mut self) {
+        if let Some((index, _)) = &mut self.picker {
+            let selected = index.selected().unwrap_or(0);
+            index.select(Some(selected.saturating_sub(1)));
+        }
+    }
+
+    pub fn picker_down(&mut self) {
+        if let Some((index, _)) = &mut self.picker {
+            let selected = index.selected().unwrap_or(0);
+            if selected + 1 < self.names.len() {
+                index.select(Some(selected + 1));
+            }
+        }
+    }
+}
@@ -0,0 +1,684 @@
+use std::{
+    cmp::Ordering,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use rand::seq::SliceRandom;
+
+use crate::{
+    media::{stats::Stats, Metadata},
+    utils,
+};
+
+// 智能播放列表的查询 ; 例如: genre = Jazz AND year < 1970 LIMIT 50 RANDOM
+//
+// 条件: 字段 运算符 值，值中有空格时使用引号
+// 运算符: = != < <= > >= ~(包含)
+// 组合: AND OR NOT ()
+// 短语: never played ; added in the last 30 days ; favorite
+pub struct Query {
+    expr: Option<Expr>,       // 没有条件时匹配所有文件
+    pub limit: Option<usize>, // 最多选取的数量
+    pub random: bool,         // 随机抽样 ; 否则按路径排序后选取
+}
+
+// 查询的字段
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+enum Field {
+    Title,
+    Artist,
+    Album,
+    AlbumArtist,
+    Composer,
+    Genre,
+    Name, // 文件名
+    Path, // 文件路径
+    Year,
+    Track,
+    Disc,
+    Duration, // 秒，也可以写成 分:秒
+    Added,    // 添加到现在的天数
+    Plays,    // 播放次数
+    Skips,    // 跳过次数
+    Rating,   // 评分 ; 1-5
+    Favorite, // 收藏 ; 1 或 0
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Text(Field, Op, String),
+    Number(Field, Op, f64),
+}
+
+// 查询的文件 ; 播放统计由调用方提供
+pub struct Track<'a> {
+    pub path: &'a Path,
+    pub metadata: &'a Metadata,
+    pub duration: Option<Duration>, // 只在查询用到时长时读取
+    pub added: Option<SystemTime>,  // 文件的创建时间，没有时使用修改时间
+    pub stats: Stats,               // 播放统计
+}
+
+#[derive(PartialEq, Clone, Debug)]
+enum Token {
+    Open,
+    Close,
+    Op(Op),
+    Word(String),   // 没有引号的单词
+    Quoted(String), // 引号中的内容
+}
+
+impl Field {
+    fn parse(word: &str) -> Option<Self> {
+        Some(match word.to_lowercase().as_str() {
+            "title" => Self::Title,
+            "artist" => Self::Artist,
+            "album" => Self::Album,
+            "album_artist" | "albumartist" => Self::AlbumArtist,
+            "composer" => Self::Composer,
+            "genre" => Self::Genre,
+            "name" | "file" => Self::Name,
+            "path" => Self::Path,
+            "year" => Self::Year,
+            "track" => Self::Track,
+            "disc" => Self::Disc,
+            "duration" | "length" => Self::Duration,
+            "added" => Self::Added,
+            "plays" | "played" | "playcount" => Self::Plays,
+            "skips" | "skipped" => Self::Skips,
+            "rating" | "stars" => Self::Rating,
+            "favorite" | "favourite" => Self::Favorite,
+            _ => return None,
+        })
+    }
+
+    fn is_number(&self) -> bool {
+        matches!(
+            self,
+            Self::Year
+                | Self::Track
+                | Self::Disc
+                | Self::Duration
+                | Self::Added
+                | Self::Plays
+                | Self::Skips
+                | Self::Rating
+                | Self::Favorite
+        )
+    }
+
+    fn text(&self, track: &Track) -> Option<String> {
+        let metadata = track.metadata;
+        match self {
+            Self::Title => metadata.title.clone(),
+            Self::Artist => metadata.artist.clone(),
+            Self::Album => metadata.album.clone(),
+            Self::AlbumArtist => metadata.album_artist.clone(),
+            Self::Composer => metadata.composer.clone(),
+            Self::Genre => metadata.genre.clone(),
+            Self::Name => Some(track.path.file_name()?.to_string_lossy().to_string()),
+            Self::Path => Some(track.path.to_string_lossy().to_string()),
+            _ => None,
+        }
+    }
+
+    fn number(&self, track: &Track) -> Option<f64> {
+        let metadata = track.metadata;
+        match self {
+            Self::Year => metadata.year.map(|it| it as f64),
+            Self::Track => metadata.track.map(|it| it as f64),
+            Self::Disc => metadata.disc.map(|it| it as f64),
+            Self::Duration => track.duration.map(|it| it.as_secs_f64()),
+            Self::Added => {
+                let elapsed = track.added?.elapsed().unwrap_or_default();
+                Some(elapsed.as_secs_f64() / 86400.0)
+            }
+            Self::Plays => Some(track.stats.plays as f64),
+            Self::Skips => Some(track.stats.skips as f64),
+            Self::Rating => track.stats.rating.map(|it| it as f64),
+            Self::Favorite => Some(if track.stats.favorite { 1.0 } else { 0.0 }),
+            _ => None,
+        }
+    }
+}
+
+impl Op {
+    fn test(&self, ordering: Ordering) -> bool {
+        match self {
+            Self::Eq | Self::Contains => ordering == Ordering::Equal,
+            Self::Ne => ordering != Ordering::Equal,
+            Self::Lt => ordering == Ordering::Less,
+            Self::Le => ordering != Ordering::Greater,
+            Self::Gt => ordering == Ordering::Greater,
+            Self::Ge => ordering != Ordering::Less,
+        }
+    }
+}
+
+impl Expr {
+    fn matches(&self, track: &Track) -> bool {
+        match self {
+            Self::And(a, b) => a.matches(track) && b.matches(track),
+            Self::Or(a, b) => a.matches(track) || b.matches(track),
+            Self::Not(a) => !a.matches(track),
+            // 文本比较不区分大小写 ; 没有该字段时只有 != 成立
+            Self::Text(field, op, value) => match field.text(track) {
+                Some(text) => {
+                    let text = text.to_lowercase();
+                    if *op == Op::Contains {
+                        text.contains(value.as_str())
+                    } else {
+                        op.test(text.as_str().cmp(value.as_str()))
+                    }
+                }
+                None => *op == Op::Ne,
+            },
+            Self::Number(field, op, value) => match field.number(track) {
+                Some(number) => number
+                    .partial_cmp(value)
+                    .is_some_and(|ordering| op.test(ordering)),
+                None => *op == Op::Ne,
+            },
+        }
+    }
+
+    fn uses(&self, target: Field) -> bool {
+        match self {
+            Self::And(a, b) | Self::Or(a, b) => a.uses(target) || b.uses(target),
+            Self::Not(a) => a.uses(target),
+            Self::Text(field, _, _) | Self::Number(field, _, _) => *field == target,
+        }
+    }
+}
+
+impl Query {
+    // 解析查询 ; 失败时返回出错位置的内容
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = if parser.is_end() {
+            None
+        } else {
+            Some(parser.or()?)
+        };
+        let mut query = Self {
+            expr,
+            limit: None,
+            random: false,
+        };
+        while let Some(token) = parser.next() {
+            match token {
+                Token::Word(w) if w.eq_ignore_ascii_case("limit") => {
+                    let n = parser.word()?;
+                    query.limit = Some(n.parse().map_err(|_| n)?);
+                }
+                Token::Word(w) if w.eq_ignore_ascii_case("random") => query.random = true,
+                token => return Err(token.to_string()),
+            }
+        }
+        Ok(query)
+    }
+
+    pub fn matches(&self, track: &Track) -> bool {
+        // 没有条件时匹配所有文件
+        match &self.expr {
+            Some(expr) => expr.matches(track),
+            None => true,
+        }
+    }
+
+    // 是否需要读取时长 ; 读取时长较慢
+    pub fn needs_duration(&self) -> bool {
+        self.expr
+            .as_ref()
+            .is_some_and(|it| it.uses(Field::Duration))
+    }
+
+    // 从匹配的文件中选取 ; 随机抽样或按路径排序
+    pub fn select(&self, mut files: Vec<PathBuf>) -> Vec<PathBuf> {
+        if self.random {
+            files.shuffle(&mut rand::thread_rng());
+        } else {
+            files.sort_by(|a, b| utils::natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
+        }
+        if let Some(limit) = self.limit {
+            files.truncate(limit);
+        }
+        files
+    }
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Open => write!(f, "("),
+            Self::Close => write!(f, ")"),
+            Self::Op(op) => write!(f, "{:?}", op),
+            Self::Word(w) => write!(f, "{}", w),
+            Self::Quoted(w) => write!(f, "\"{}\"", w),
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            _ if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '~' => Token::Op(Op::Contains),
+            '=' => {
+                chars.next_if_eq(&'=');
+                Token::Op(Op::Eq)
+            }
+            '!' if chars.next_if_eq(&'=').is_some() => Token::Op(Op::Ne),
+            '<' if chars.next_if_eq(&'=').is_some() => Token::Op(Op::Le),
+            '<' => Token::Op(Op::Lt),
+            '>' if chars.next_if_eq(&'=').is_some() => Token::Op(Op::Ge),
+            '>' => Token::Op(Op::Gt),
+            '"' | '\'' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some(it) if it == c => break,
+                        Some(it) => value.push(it),
+                        None => return Err(format!("{}{}", c, value)),
+                    }
+                }
+                Token::Quoted(value)
+            }
+            _ => {
+                let mut word = String::from(c);
+                while let Some(it) =
+                    chars.next_if(|it| !it.is_whitespace() && !"()=!<>~".contains(*it))
+                {
+                    word.push(it);
+                }
+                Token::Word(word)
+            }
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn is_end(&self) -> bool {
+        self.peek().is_none() || self.peek_keyword("limit") || self.peek_keyword("random")
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(w)) if w.eq_ignore_ascii_case(keyword))
+    }
+
+    // 跳过可选的关键字
+    fn skip_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.peek_keyword(keyword);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), String> {
+        if self.skip_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn word(&mut self) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Word(w)) => Ok(w),
+            Some(token) => Err(token.to_string()),
+            None => Err(String::default()),
+        }
+    }
+
+    fn unexpected(&self) -> String {
+        self.peek().map(|it| it.to_string()).unwrap_or_default()
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.skip_keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        while self.skip_keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.skip_keyword("not") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.peek() == Some(&Token::Open) {
+            self.pos += 1;
+            let expr = self.or()?;
+            return match self.next() {
+                Some(Token::Close) => Ok(expr),
+                Some(token) => Err(token.to_string()),
+                None => Err(String::default()),
+            };
+        }
+        // never played
+        if self.skip_keyword("never") {
+            self.expect_keyword("played")?;
+            return Ok(Expr::Number(Field::Plays, Op::Eq, 0.0));
+        }
+        // favorite ; 没有运算符时表示已收藏
+        if self.peek_keyword("favorite")
+            && !matches!(self.tokens.get(self.pos + 1), Some(Token::Op(_)))
+        {
+            self.pos += 1;
+            return Ok(Expr::Number(Field::Favorite, Op::Eq, 1.0));
+        }
+        // added in the last 30 days
+        if self.peek_keyword("added")
+            && !matches!(self.tokens.get(self.pos + 1), Some(Token::Op(_)))
+        {
+            self.pos += 1;
+            self.expect_keyword("in")?;
+            self.skip_keyword("the");
+            self.expect_keyword("last")?;
+            let n = self.word()?;
+            let days: f64 = n.parse().map_err(|_| n)?;
+            if !self.skip_keyword("days") {
+                self.expect_keyword("day")?;
+            }
+            return Ok(Expr::Number(Field::Added, Op::Le, days));
+        }
+        self.condition()
+    }
+
+    fn condition(&mut self) -> Result<Expr, String> {
+        let name = self.word()?;
+        let field = Field::parse(&name).ok_or(name)?;
+        let op = match self.next() {
+            Some(Token::Op(op)) => op,
+            Some(token) => return Err(token.to_string()),
+            None => return Err(String::default()),
+        };
+        let value = self.value()?;
+        if field.is_number() {
+            if op == Op::Contains {
+                return Err(value);
+            }
+            let number = parse_number(field, &value).ok_or(value)?;
+            Ok(Expr::Number(field, op, number))
+        } else {
+            Ok(Expr::Text(field, op, value.to_lowercase()))
+        }
+    }
+
+    // 值 ; 引号中的内容，或者到下一个关键字之前的单词
+    fn value(&mut self) -> Result<String, String> {
+        if let Some(Token::Quoted(value)) = self.peek() {
+            let value = value.clone();
+            self.pos += 1;
+            return Ok(value);
+        }
+        let mut words = vec![];
+        while let Some(Token::Word(w)) = self.peek() {
+            if ["and", "or", "limit", "random"]
+                .iter()
+                .any(|it| w.eq_ignore_ascii_case(it))
+            {
+                break;
+            }
+            words.push(w.clone());
+            self.pos += 1;
+        }
+        if words.is_empty() {
+            Err(self.unexpected())
+        } else {
+            Ok(words.join(" "))
+        }
+    }
+}
+
+// 数字 ; 时长可以写成 分:秒
+fn parse_number(field: Field, value: &str) -> Option<f64> {
+    if field == Field::Duration {
+        if let Some((m, s)) = value.split_once(':') {
+            return Some(m.parse::<f64>().ok()? * 60.0 + s.parse::<f64>().ok()?);
+        }
+    }
+    value.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Song {
+        path: PathBuf,
+        metadata: Metadata,
+        duration: Option<Duration>,
+        added: Option<SystemTime>,
+        stats: Stats,
+    }
+
+    impl Song {
+        fn new(genre: &str, year: i32) -> Self {
+            Self {
+                path: PathBuf::from("/music/song.mp3"),
+                metadata: Metadata {
+                    genre: Some(genre.to_string()),
+                    year: Some(year),
+                    ..Default::default()
+                },
+                duration: None,
+                added: None,
+                stats: Stats::default(),
+            }
+        }
+
+        fn track(&self) -> Track<'_> {
+            Track {
+                path: &self.path,
+                metadata: &self.metadata,
+                duration: self.duration,
+                added: self.added,
+                stats: self.stats.clone(),
+            }
+        }
+    }
+
+    fn matches(query: &str, song: &Song) -> bool {
+        Query::parse(query).unwrap().matches(&song.track())
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let rock = Song::new("Rock", 2000);
+        let query = "genre = rock OR genre = jazz AND year < 1970";
+        assert!(matches(query, &rock));
+        assert!(!matches(query, &Song::new("Jazz", 2000)));
+        assert!(matches(query, &Song::new("Jazz", 1959)));
+    }
+
+    #[test]
+    fn parentheses_group() {
+        let query = "(genre = rock OR genre = jazz) AND year < 1970";
+        assert!(!matches(query, &Song::new("Rock", 2000)));
+        assert!(matches(query, &Song::new("Rock", 1969)));
+    }
+
+    #[test]
+    fn not() {
+        let rock = Song::new("Rock", 2000);
+        assert!(!matches("NOT genre = rock", &rock));
+        assert!(matches("not genre = jazz", &rock));
+        assert!(matches("NOT NOT genre = rock", &rock));
+        assert!(matches("NOT (genre = jazz OR year > 2010)", &rock));
+    }
+
+    #[test]
+    fn unquoted_values_end_at_keywords() {
+        let mut song = Song::new("Jazz", 1959);
+        song.metadata.artist = Some("Miles Davis".to_string());
+        assert!(matches("artist = miles davis AND year = 1959", &song));
+        assert!(matches("artist = Miles Davis OR year = 2000", &song));
+        assert!(!matches("artist = miles AND year = 1959", &song));
+        assert!(matches("artist ~ davis LIMIT 1", &song));
+    }
+
+    #[test]
+    fn quoted_values_keep_keywords() {
+        let mut song = Song::new("Jazz", 1959);
+        song.metadata.title = Some("Black and Blue".to_string());
+        assert!(matches("title = \"black and blue\"", &song));
+        assert!(matches("title ~ 'and blue' AND year = 1959", &song));
+        assert!(!matches("title = \"black\"", &song));
+    }
+
+    #[test]
+    fn missing_text_field_only_matches_not_equal() {
+        let song = Song::new("Jazz", 1959);
+        assert!(!matches("composer = bach", &song));
+        assert!(!matches("composer ~ bach", &song));
+        assert!(matches("composer != bach", &song));
+    }
+
+    #[test]
+    fn duration_in_minutes_and_seconds() {
+        let mut song = Song::new("Jazz", 1959);
+        let query = Query::parse("duration < 3:30").unwrap();
+        assert!(query.needs_duration());
+        // 没有读取时长时不匹配
+        assert!(!query.matches(&song.track()));
+        song.duration = Some(Duration::from_secs(209));
+        assert!(query.matches(&song.track()));
+        song.duration = Some(Duration::from_secs(210));
+        assert!(!query.matches(&song.track()));
+        assert!(matches("duration >= 210", &song));
+        assert!(!Query::parse("year < 1970").unwrap().needs_duration());
+    }
+
+    #[test]
+    fn never_played() {
+        let mut song = Song::new("Jazz", 1959);
+        assert!(matches("never played", &song));
+        song.stats.plays = 1;
+        assert!(!matches("never played", &song));
+        assert!(matches("NOT never played AND plays = 1", &song));
+        assert!(Query::parse("never heard").is_err());
+    }
+
+    #[test]
+    fn favorite() {
+        let mut song = Song::new("Jazz", 1959);
+        assert!(!matches("favorite", &song));
+        assert!(matches("favorite = 0", &song));
+        song.stats.favorite = true;
+        assert!(matches("favorite", &song));
+        assert!(matches("favorite AND genre = jazz", &song));
+        assert!(matches("favourite = 1", &song));
+    }
+
+    #[test]
+    fn added_in_the_last_days() {
+        let day = Duration::from_secs(86400);
+        let mut song = Song::new("Jazz", 1959);
+        assert!(!matches("added in the last 7 days", &song));
+        song.added = Some(SystemTime::now() - day * 3);
+        assert!(matches("added in the last 7 days", &song));
+        assert!(matches("added in last 7 day", &song));
+        assert!(!matches("added in the last 2 days", &song));
+        assert!(matches("added > 2", &song));
+        assert_eq!(
+            Query::parse("added in the last week").err(),
+            Some("week".to_string())
+        );
+    }
+
+    #[test]
+    fn limit_and_random() {
+        let query = Query::parse("genre = jazz LIMIT 50 RANDOM").unwrap();
+        assert_eq!(query.limit, Some(50));
+        assert!(query.random);
+        let query = Query::parse("random").unwrap();
+        assert_eq!(query.limit, None);
+        assert!(query.random);
+        assert!(query.matches(&Song::new("Rock", 2000).track()));
+        assert_eq!(Query::parse("limit many").err(), Some("many".to_string()));
+    }
+
+    #[test]
+    fn select_sorts_naturally_and_limits() {
+        let files: Vec<PathBuf> = ["b/10.mp3", "b/2.mp3", "a.mp3"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        let query = Query::parse("limit 2").unwrap();
+        assert_eq!(
+            query.select(files.clone()),
+            vec![PathBuf::from("a.mp3"), PathBuf::from("b/2.mp3")]
+        );
+        let query = Query::parse("random").unwrap();
+        let mut selected = query.select(files.clone());
+        selected.sort();
+        let mut files = files;
+        files.sort();
+        assert_eq!(selected, files);
+    }
+
+    #[test]
+    fn errors() {
+        // 未知的字段
+        assert_eq!(Query::parse("mood = happy").err(), Some("mood".to_string()));
+        // 数字字段不能使用 ~
+        assert_eq!(Query::parse("year ~ 19").err(), Some("19".to_string()));
+        assert_eq!(Query::parse("year = soon").err(), Some("soon".to_string()));
+        // 没有结束的引号
+        assert_eq!(
+            Query::parse("title = \"blue in").err(),
+            Some("\"blue in".to_string())
+        );
+        // 没有结束的括号
+        assert_eq!(Query::parse("(genre = jazz").err(), Some(String::new()));
+        assert!(Query::parse("genre jazz").is_err());
+        assert!(Query::parse("genre =").is_err());
+        assert!(Query::parse("genre = jazz )").is_err());
+    }
+}
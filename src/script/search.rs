@@ -0,0 +1,354 @@
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
+
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+use ratatui::widgets::ListState;
+
+use crate::media::{Media, Metadata};
+
+// 搜索的列表
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum SearchTarget {
+    Explorer, // 本地文件夹
+    PlayList, // 播放列表
+    Lyrics,   // 当前歌曲的歌词
+}
+
+// 搜索结果
+pub struct SearchMatch {
+    pub index: usize,          // 在原列表中的索引
+    pub score: i64,            // 匹配得分
+    pub text: String,          // 参与匹配的文本
+    pub positions: Vec<usize>, // 匹配到的字符位置
+}
+
+pub struct Search {
+    pub input: String,              // 搜索内容
+    pub editing: bool,              // 是否正在输入
+    pub target: SearchTarget,       // 搜索的列表
+    pub matches: Vec<SearchMatch>,  // 按得分排序的搜索结果
+    pub index: ListState,           // 搜索结果列表状态
+    tags: HashMap<PathBuf, String>, // 文件的标签文本缓存 ; 由后台解析
+    pending: HashSet<PathBuf>,      // 正在后台解析的文件
+    matcher: SkimMatcherV2,
+}
+
+impl Search {
+    pub fn new() -> Self {
+        Self {
+            input: String::default(),
+            editing: false,
+            target: SearchTarget::Explorer,
+            matches: vec![],
+            index: ListState::default(),
+            tags: HashMap::new(),
+            pending: HashSet::new(),
+            matcher: SkimMatcherV2::default(),
+        }
+    }
+
+    // 是否正在过滤列表
+    pub fn is_active(&self, target: SearchTarget) -> bool {
+        self.target == target && (self.editing || !self.input.is_empty())
+    }
+
+    // 开始输入 ; 保留上一次的搜索内容
+    pub fn start(&mut self, target: SearchTarget) {
+        if self.target != target {
+            self.input.clear();
+        }
+        self.target = target;
+        self.editing = true;
+    }
+
+    // 结束搜索
+    pub fn clear(&mut self) {
+        self.input.clear();
+        self.editing = false;
+        self.matches.clear();
+        self.index.select(None);
+    }
+
+    // 对候选文本进行模糊匹配 ; 得分相同时保持原列表顺序
+    pub fn update<I>(&mut self, candidates: I)
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut matches: Vec<SearchMatch> = candidates
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, text)| {
+                if self.input.is_empty() {
+                    return Some(SearchMatch {
+                        index,
+                        score: 0,
+                        text,
+                        positions: vec![],
+                    });
+                }
+                self.matcher
+                    .fuzzy_indices(&text, &self.input)
+                    .map(|(score, positions)| SearchMatch {
+                        index,
+                        score,
+                        text,
+                        positions,
+                    })
+            })
+            .collect();
+        matches.sort_by_key(|it| Reverse(it.score));
+        self.matches = matches;
+
+        let len = self.matches.len();
+        match self.index.selected() {
+            _ if len == 0 => self.index.select(None),
+            Some(selected) if selected >= len => self.index.select(Some(len - 1)),
+            None => self.index.select(Some(0)),
+            _ => {}
+        }
+    }
+
+    // 当前选中结果在原列表中的索引
+    pub fn selected(&self) -> Option<usize> {
+        self.index
+            .selected()
+            .and_then(|i| self.matches.get(i))
+            .map(|it| it.index)
+    }
+
+    // 下一个匹配项
+    pub fn next(&mut self) {
+        let len = self.matches.len();
+        if len > 0 {
+            let i = self.index.selected().map_or(0, |i| (i + 1) % len);
+            self.index.select(Some(i));
+        }
+    }
+
+    // 上一个匹配项
+    pub fn prev(&mut self) {
+        let len = self.matches.len();
+        if len > 0 {
+            let i = self
+                .index
+                .selected()
+                .map_or(len - 1, |i| (i + len - 1) % len);
+            self.index.select(Some(i));
+        }
+    }
+
+    // 文件的标签文本 ; 没有缓存时返回None，由调用方在后台解析
+    pub fn file_tag_text(&self, path: &PathBuf) -> Option<&String> {
+        self.tags.get(path)
+    }
+
+    // 记录需要在后台解析的文件 ; 已经在解析中时返回false
+    pub fn request(&mut self, path: PathBuf) -> bool {
+        self.pending.insert(path)
+    }
+
+    // 记录后台解析完成的标签文本 ; 等待的文件全部完成时返回true，需要刷新搜索结果
+    pub fn loaded(&mut self, media: &Media) -> bool {
+        let path = media.get_path();
+        let text = Self::tag_text(media.get_metadata());
+        self.tags.insert(path.clone(), text);
+        self.pending.remove(path) && self.pending.is_empty()
+    }
+
+    // 文件标签修改后重新读取
+    pub fn forget(&mut self, path: &PathBuf) {
+        self.tags.remove(path);
+    }
+
+    // 标题、表演者、专辑
+    pub fn tag_text(metadata: &Metadata) -> String {
+        [&metadata.title, &metadata.artist, &metadata.album]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect::<Vec<&str>>()
+            .join(" · ")
+    }
+
+    // 拼接文件名和标签文本
+    pub fn candidate(name: &str, tag_text: &str) -> String {
+        if tag_text.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}  {}", name, tag_text)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(search: &Search) -> Vec<&str> {
+        search.matches.iter().map(|it| it.text.as_str()).collect()
+    }
+
+    fn candidates() -> Vec<String> {
+        [
+            "Blue in Green",
+            "So What",
+            "Freddie Freeloader",
+            "All Blues",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect()
+    }
+
+    #[test]
+    fn empty_input_keeps_all_in_order() {
+        let mut search = Search::new();
+        search.start(SearchTarget::PlayList);
+        search.update(candidates());
+        assert_eq!(
+            names(&search),
+            [
+                "Blue in Green",
+                "So What",
+                "Freddie Freeloader",
+                "All Blues"
+            ]
+        );
+        assert_eq!(search.selected(), Some(0));
+    }
+
+    #[test]
+    fn filters_and_maps_back_to_original_index() {
+        let mut search = Search::new();
+        search.start(SearchTarget::PlayList);
+        search.input = "blue".to_string();
+        search.update(candidates());
+        let mut found = names(&search);
+        found.sort();
+        assert_eq!(found, ["All Blues", "Blue in Green"]);
+        // 结果按得分排序，选中项对应原列表中的位置
+        for (i, it) in search.matches.iter().enumerate() {
+            search.index.select(Some(i));
+            assert_eq!(search.selected(), Some(it.index));
+            assert_eq!(candidates()[it.index], it.text);
+        }
+    }
+
+    #[test]
+    fn fuzzy_positions_point_at_matched_chars() {
+        let mut search = Search::new();
+        search.start(SearchTarget::Explorer);
+        search.input = "fdl".to_string();
+        search.update(candidates());
+        assert_eq!(names(&search), ["Freddie Freeloader"]);
+        let text: Vec<char> = search.matches[0].text.chars().collect();
+        let matched: String = search.matches[0]
+            .positions
+            .iter()
+            .map(|i| text[*i].to_ascii_lowercase())
+            .collect();
+        assert_eq!(matched, "fdl");
+    }
+
+    #[test]
+    fn equal_scores_keep_original_order() {
+        let mut search = Search::new();
+        search.start(SearchTarget::PlayList);
+        search.input = "a".to_string();
+        let items = vec!["a 1".to_string(), "a 2".to_string(), "a 3".to_string()];
+        search.update(items);
+        let indexes: Vec<usize> = search.matches.iter().map(|it| it.index).collect();
+        assert_eq!(indexes, [0, 1, 2]);
+    }
+
+    #[test]
+    fn selection_is_clamped_when_results_shrink() {
+        let mut search = Search::new();
+        search.start(SearchTarget::PlayList);
+        search.update(candidates());
+        search.index.select(Some(3));
+        assert_eq!(search.selected(), Some(3));
+
+        // 过滤后只剩两项，选中最后一项
+        search.input = "blue".to_string();
+        search.update(candidates());
+        assert_eq!(search.index.selected(), Some(1));
+        assert!(search.selected().is_some());
+
+        // 没有结果时没有选中项
+        search.input = "zzz".to_string();
+        search.update(candidates());
+        assert_eq!(search.index.selected(), None);
+        assert_eq!(search.selected(), None);
+
+        // 重新有结果时选中第一项
+        search.input.clear();
+        search.update(candidates());
+        assert_eq!(search.selected(), Some(0));
+    }
+
+    #[test]
+    fn next_and_prev_wrap_around() {
+        let mut search = Search::new();
+        search.start(SearchTarget::PlayList);
+        search.update(candidates());
+        search.prev();
+        assert_eq!(search.selected(), Some(3));
+        search.next();
+        assert_eq!(search.selected(), Some(0));
+    }
+
+    #[test]
+    fn start_keeps_input_only_for_same_target() {
+        let mut search = Search::new();
+        search.start(SearchTarget::PlayList);
+        search.input = "so".to_string();
+        search.editing = false;
+        assert!(search.is_active(SearchTarget::PlayList));
+        assert!(!search.is_active(SearchTarget::Explorer));
+        search.start(SearchTarget::PlayList);
+        assert_eq!(search.input, "so");
+        search.start(SearchTarget::Explorer);
+        assert!(search.input.is_empty());
+    }
+
+    #[test]
+    fn candidate_joins_name_and_tags() {
+        let metadata = Metadata {
+            title: Some("So What".to_string()),
+            artist: Some("Miles Davis".to_string()),
+            ..Default::default()
+        };
+        let tag_text = Search::tag_text(&metadata);
+        assert_eq!(tag_text, "So What · Miles Davis");
+        assert_eq!(
+            Search::candidate("01.mp3", &tag_text),
+            "01.mp3  So What · Miles Davis"
+        );
+        assert_eq!(Search::candidate("01.mp3", ""), "01.mp3");
+    }
+
+    #[test]
+    fn tag_text_waits_for_every_requested_file() {
+        let mut search = Search::new();
+        let a = PathBuf::from("/music/a.mp3");
+        let b = PathBuf::from("/music/b.mp3");
+        assert_eq!(search.file_tag_text(&a), None);
+        assert!(search.request(a.clone()));
+        assert!(!search.request(a.clone()));
+        assert!(search.request(b.clone()));
+
+        let media = |path: &PathBuf| Media::new_local_file_placeholder(path.clone()).unwrap();
+        assert!(!search.loaded(&media(&a)));
+        assert_eq!(search.file_tag_text(&a), Some(&String::new()));
+        // 没有请求的文件也缓存，但不触发刷新
+        assert!(!search.loaded(&media(&PathBuf::from("/music/c.mp3"))));
+        assert!(search.loaded(&media(&b)));
+
+        search.forget(&a);
+        assert_eq!(search.file_tag_text(&a), None);
+    }
+}
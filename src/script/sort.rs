@@ -0,0 +1,201 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::DirEntry,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    config::Config,
+    media::{stats::StatsDb, Media},
+    utils,
+};
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum SortOrder {
+    Asc,  // 升序 ; 默认
+    Desc, // 降序
+}
+
+// 排序依据
+#[derive(PartialEq, Clone, Copy)]
+pub enum SortMode {
+    Name,     // 文件名 ; 默认
+    Natural,  // 自然顺序 ; "2" 在 "10" 前面
+    Modified, // 修改时间
+    Size,     // 文件大小
+    Duration, // 播放时长
+    Track,    // 标签中的曲目编号
+    Plays,    // 播放次数
+    Rating,   // 评分
+}
+
+impl SortMode {
+    const ALL: [SortMode; 8] = [
+        SortMode::Name,
+        SortMode::Natural,
+        SortMode::Modified,
+        SortMode::Size,
+        SortMode::Duration,
+        SortMode::Track,
+        SortMode::Plays,
+        SortMode::Rating,
+    ];
+
+    // 下一个排序依据
+    fn next(&self) -> Self {
+        let i = Self::ALL.iter().position(|it| it == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    pub fn name(&self) -> &'static str {
+        Config::SORT_MODE_NAMES[*self as usize]
+    }
+}
+
+// 文件夹列表的排序方式
+pub struct ExplorerSort {
+    pub mode: SortMode,
+    pub order: SortOrder,
+    pub show_hidden: bool,                           // 是否显示隐藏文件夹
+    tags: HashMap<PathBuf, (Option<u32>, Duration)>, // 曲目编号和时长 ; 由后台解析，缓存起来
+    pending: HashSet<PathBuf>,                       // 正在后台解析的文件
+}
+
+impl ExplorerSort {
+    pub fn new() -> Self {
+        Self {
+            mode: SortMode::Name,
+            order: SortOrder::Asc,
+            show_hidden: false,
+            tags: HashMap::new(),
+            pending: HashSet::new(),
+        }
+    }
+
+    pub fn next_mode(&mut self) {
+        self.mode = self.mode.next();
+    }
+
+    pub fn toggle_order(&mut self) {
+        self.order = match self.order {
+            SortOrder::Asc => SortOrder::Desc,
+            SortOrder::Desc => SortOrder::Asc,
+        };
+    }
+
+    pub fn toggle_hidden(&mut self) {
+        self.show_hidden = !self.show_hidden;
+    }
+
+    // 文件标签修改后重新读取
+    pub fn forget(&mut self, path: &PathBuf) {
+        self.tags.remove(path);
+    }
+
+    // 记录后台解析完成的曲目编号和时长 ; 等待的文件全部完成时返回true，需要重新排序
+    pub fn loaded(&mut self, media: &Media) -> bool {
+        let path = media.get_path();
+        let tags = (media.get_metadata().track, media.get_duration());
+        self.tags.insert(path.clone(), tags);
+        self.pending.remove(path) && self.pending.is_empty()
+    }
+
+    // 列表标题中展示的排序方式 ; 名称 ↑
+    pub fn title(&self) -> String {
+        let order = match self.order {
+            SortOrder::Asc => "↑",
+            SortOrder::Desc => "↓",
+        };
+        let hidden = if self.show_hidden { " .*" } else { "" };
+        format!("{} {}{}", self.mode.name(), order, hidden)
+    }
+
+    // 排序文件夹和文件 ; 时长、大小、曲目、播放统计只对文件有意义，此时文件夹按名称升序
+    // 返回需要在后台解析的文件 ; 解析完成前按名称排序
+    pub fn sort(
+        &mut self,
+        dirs: &mut [DirEntry],
+        files: &mut [DirEntry],
+        stats: &StatsDb,
+    ) -> Vec<PathBuf> {
+        match self.mode {
+            SortMode::Name | SortMode::Natural | SortMode::Modified => {
+                self.sort_entries(dirs, self.mode, self.order, stats)
+            }
+            _ => self.sort_entries(dirs, SortMode::Name, SortOrder::Asc, stats),
+        };
+        self.sort_entries(files, self.mode, self.order, stats)
+    }
+
+    fn sort_entries(
+        &mut self,
+        entries: &mut [DirEntry],
+        mode: SortMode,
+        order: SortOrder,
+        stats: &StatsDb,
+    ) -> Vec<PathBuf> {
+        let mut requests = vec![];
+        let mut mode = mode;
+        if let SortMode::Duration | SortMode::Track = mode {
+            let missing: Vec<PathBuf> = entries
+                .iter()
+                .map(|it| it.path())
+                .filter(|it| !self.tags.contains_key(it))
+                .collect();
+            if !missing.is_empty() {
+                mode = SortMode::Name;
+                requests = missing
+                    .into_iter()
+                    .filter(|it| self.pending.insert(it.clone()))
+                    .collect();
+            }
+        }
+        let tags = &self.tags;
+        entries.sort_by(|a, b| {
+            let ordering = match mode {
+                SortMode::Name => a.file_name().cmp(&b.file_name()),
+                SortMode::Natural => utils::natural_cmp(
+                    &a.file_name().to_string_lossy(),
+                    &b.file_name().to_string_lossy(),
+                ),
+                SortMode::Modified => Self::modified(a).cmp(&Self::modified(b)),
+                SortMode::Size => Self::size(a).cmp(&Self::size(b)),
+                SortMode::Duration => tags
+                    .get(&a.path())
+                    .map(|it| it.1)
+                    .cmp(&tags.get(&b.path()).map(|it| it.1)),
+                // 没有曲目编号的排在最后
+                SortMode::Track => {
+                    let track = |it: &DirEntry| {
+                        tags.get(&it.path()).and_then(|it| it.0).unwrap_or(u32::MAX)
+                    };
+                    track(a).cmp(&track(b))
+                }
+                SortMode::Plays => {
+                    let plays = |it: &DirEntry| stats.get(&it.path()).map_or(0, |it| it.plays);
+                    plays(a).cmp(&plays(b))
+                }
+                SortMode::Rating => {
+                    let rating = |it: &DirEntry| stats.get(&it.path()).and_then(|it| it.rating);
+                    rating(a).cmp(&rating(b))
+                }
+            };
+            // 相同时按文件名排序
+            let ordering = ordering.then_with(|| a.file_name().cmp(&b.file_name()));
+            match order {
+                SortOrder::Asc => ordering,
+                SortOrder::Desc => ordering.reverse(),
+            }
+        });
+        requests
+    }
+
+    fn modified(entry: &DirEntry) -> Option<SystemTime> {
+        entry.metadata().and_then(|it| it.modified()).ok()
+    }
+
+    fn size(entry: &DirEntry) -> u64 {
+        entry.metadata().map(|it| it.len()).unwrap_or(0)
+    }
+}
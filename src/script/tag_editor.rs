@@ -0,0 +1,91 @@
+use std::path::PathBuf;
+
+use ratatui::widgets::ListState;
+
+use crate::media::{Metadata, TagField};
+
+// 信息面板的标签编辑
+pub struct TagEditor {
+    pub targets: Vec<PathBuf>,           // 要修改的文件 ; 批量修改时有多个
+    pub fields: Vec<(TagField, String)>, // 字段和值 ; 初始值来自第一个文件
+    changed: Vec<bool>,                  // 字段是否修改过
+    pub index: ListState,                // 当前字段
+    pub editing: bool,                   // 是否正在输入
+    pub input: String,                   // 输入内容
+}
+
+impl TagEditor {
+    pub fn new(targets: Vec<PathBuf>, metadata: &Metadata) -> Self {
+        let mut index = ListState::default();
+        index.select(Some(0));
+        Self {
+            targets,
+            fields: TagField::ALL
+                .iter()
+                .map(|it| (*it, it.value(metadata)))
+                .collect(),
+            changed: vec![false; TagField::ALL.len()],
+            index,
+            editing: false,
+            input: String::default(),
+        }
+    }
+
+    // 当前字段
+    pub fn selected(&self) -> usize {
+        self.index.selected().unwrap_or(0)
+    }
+
+    pub fn up(&mut self) {
+        self.index.select(Some(self.selected().saturating_sub(1)));
+    }
+
+    pub fn down(&mut self) {
+        let i = self.selected() + 1;
+        if i < self.fields.len() {
+            self.index.select(Some(i));
+        }
+    }
+
+    // 开始编辑当前字段
+    pub fn start_edit(&mut self) {
+        self.input = Self::display(&self.fields[self.selected()].1);
+        self.editing = true;
+    }
+
+    // 确认输入
+    pub fn confirm(&mut self) {
+        let i = self.selected();
+        let value = self.input.replace("\\n", "\n");
+        if value != self.fields[i].1 {
+            self.fields[i].1 = value;
+            self.changed[i] = true;
+        }
+        self.editing = false;
+    }
+
+    // 放弃输入
+    pub fn cancel_edit(&mut self) {
+        self.input.clear();
+        self.editing = false;
+    }
+
+    pub fn is_changed(&self, i: usize) -> bool {
+        self.changed[i]
+    }
+
+    // 修改过的字段
+    pub fn edits(&self) -> Vec<(TagField, String)> {
+        self.fields
+            .iter()
+            .zip(self.changed.iter())
+            .filter(|(_, &changed)| changed)
+            .map(|(it, _)| it.clone())
+            .collect()
+    }
+
+    // 单行展示 ; 歌词中的换行显示为\n
+    pub fn display(value: &str) -> String {
+        value.replace("\r\n", "\n").replace('\n', "\\n")
+    }
+}
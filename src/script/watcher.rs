@@ -0,0 +1,84 @@
+use std::{
+    collections::HashSet,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Sender,
+        Arc, Mutex,
+    },
+};
+
+use notify::{event::ModifyKind, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::events::AppEvent;
+
+// 监听当前目录和播放列表所在目录的变化 ; 变化时发送 AppEvent::FileChanged
+pub struct DirWatcher {
+    watcher: Option<RecommendedWatcher>, // 系统不支持时为None
+    dirs: HashSet<PathBuf>,              // 正在监听的目录
+    pending: Arc<AtomicBool>,            // 是否有还没处理的变化 ; 合并连续的多次变化
+    changed: Arc<Mutex<Vec<PathBuf>>>,   // 还没处理的变化的文件
+}
+
+impl DirWatcher {
+    pub fn new(events: Sender<AppEvent>) -> Self {
+        let pending = Arc::new(AtomicBool::new(false));
+        let flag = pending.clone();
+        let changed = Arc::new(Mutex::new(vec![]));
+        let paths = changed.clone();
+        let watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+            let Ok(event) = result else {
+                return;
+            };
+            // 只关心文件的增加、删除和重命名 ; 读取文件也会产生事件
+            let changed = matches!(
+                event.kind,
+                EventKind::Create(_)
+                    | EventKind::Remove(_)
+                    | EventKind::Modify(ModifyKind::Name(_))
+            );
+            if !changed {
+                return;
+            }
+            if let Ok(mut paths) = paths.lock() {
+                paths.extend(event.paths);
+            }
+            if !flag.swap(true, Ordering::SeqCst) {
+                let _ = events.send(AppEvent::FileChanged);
+            }
+        })
+        .ok();
+        Self {
+            watcher,
+            dirs: HashSet::new(),
+            pending,
+            changed,
+        }
+    }
+
+    // 更新监听的目录 ; 只处理有变化的目录
+    pub fn watch(&mut self, dirs: HashSet<PathBuf>) {
+        let Some(watcher) = &mut self.watcher else {
+            return;
+        };
+        if dirs == self.dirs {
+            return;
+        }
+        for dir in self.dirs.difference(&dirs) {
+            let _ = watcher.unwatch(dir);
+        }
+        for dir in dirs.difference(&self.dirs) {
+            let _ = watcher.watch(dir, RecursiveMode::NonRecursive);
+        }
+        self.dirs = dirs;
+    }
+
+    // 开始处理变化 ; 返回变化的文件，之后的变化会重新发送事件
+    pub fn handled(&self) -> Vec<PathBuf> {
+        self.pending.store(false, Ordering::SeqCst);
+        self.changed
+            .lock()
+            .map(|mut it| std::mem::take(&mut *it))
+            .unwrap_or_default()
+    }
+}
@@ -1,10 +1,11 @@
 use ratatui::{
     layout::Alignment,
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
     widgets::{Block, BorderType, Borders, List, ListItem},
 };
 
-use crate::config::Config;
+use crate::{config::Config, script::Search};
 
 pub struct Common;
 
@@ -13,6 +14,8 @@ impl Common {
     pub const THEME: Color = Color::Cyan;
     pub const LIGHT_THEME:Color = Color::LightCyan;
     pub const LINE_GAUGE_BACKGROUND: Color = Color::White; // 进度条颜色
+    pub const SEARCH_HIGHLIGHT: Color = Color::Yellow; // 搜索匹配字符颜色
+    pub const LYRIC_SUNG: Color = Color::Yellow; // 逐字歌词已唱部分颜色
 
     pub fn block<'a>() -> Block<'a> {
         Block::default()
@@ -29,4 +32,62 @@ impl Common {
             .highlight_style(Style::default().bg(Common::THEME)) // 高亮样式
             .highlight_symbol(Config::LIST_PREFIX_SYMBOL) // 列表前缀
     }
+
+    // 高亮搜索匹配到的字符
+    pub fn highlight<'a>(text: &str, positions: &[usize]) -> Line<'a> {
+        let highlight_style = Style::default()
+            .fg(Common::SEARCH_HIGHLIGHT)
+            .add_modifier(Modifier::BOLD);
+        let mut spans = vec![];
+        let mut segment = String::default();
+        let mut segment_matched = false;
+        for (i, c) in text.chars().enumerate() {
+            let matched = positions.contains(&i);
+            if matched != segment_matched && !segment.is_empty() {
+                spans.push(Self::highlight_span(
+                    &segment,
+                    segment_matched,
+                    highlight_style,
+                ));
+                segment.clear();
+            }
+            segment_matched = matched;
+            segment.push(c);
+        }
+        if !segment.is_empty() {
+            spans.push(Self::highlight_span(
+                &segment,
+                segment_matched,
+                highlight_style,
+            ));
+        }
+        Line::from(spans)
+    }
+    fn highlight_span<'a>(text: &str, matched: bool, style: Style) -> Span<'a> {
+        if matched {
+            Span::styled(text.to_string(), style)
+        } else {
+            Span::raw(text.to_string())
+        }
+    }
+    // 搜索中的列表标题 ; 例如: Explorer /abc (3)
+    pub fn search_title(title: &str, search: &Search) -> String {
+        let cursor = if search.editing { "_" } else { "" };
+        format!(
+            "{} /{}{} ({})",
+            title,
+            search.input,
+            cursor,
+            search.matches.len()
+        )
+    }
+    // 搜索结果列表
+    pub fn search_list<'a>(search: &Search) -> List<'a> {
+        let items: Vec<ListItem> = search
+            .matches
+            .iter()
+            .map(|it| ListItem::new(Self::highlight(&it.text, &it.positions)))
+            .collect();
+        Self::list(items)
+    }
 }
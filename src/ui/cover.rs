@@ -0,0 +1,337 @@
+use std::{collections::HashMap, io::Write, rc::Rc};
+
+use crossterm::{cursor::MoveTo, queue, terminal::window_size};
+use image::{imageops::FilterType, DynamicImage, RgbImage};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Color,
+    widgets::{Clear, Widget},
+    Frame,
+};
+
+use crate::{config::Config, media::Media, utils};
+
+// 图片输出方式
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum Protocol {
+    HalfBlocks, // unicode半块字符 + 真彩色 ; 所有终端通用
+    Kitty,      // kitty图片协议
+    Sixel,      // sixel图片协议
+}
+
+impl Protocol {
+    // 根据环境变量判断终端支持的图片协议
+    fn detect() -> Self {
+        let env = |key: &str| std::env::var(key).unwrap_or_default().to_lowercase();
+        match env(Config::GRAPHICS_PROTOCOL_ENV).as_str() {
+            "kitty" => return Protocol::Kitty,
+            "sixel" => return Protocol::Sixel,
+            "halfblocks" => return Protocol::HalfBlocks,
+            _ => {}
+        }
+
+        let term = env("TERM");
+        let term_program = env("TERM_PROGRAM");
+        if std::env::var("KITTY_WINDOW_ID").is_ok()
+            || term.contains("kitty")
+            || term.contains("ghostty")
+            || term_program == "wezterm"
+        {
+            Protocol::Kitty
+        } else if term.contains("sixel")
+            || term.contains("mlterm")
+            || term.starts_with("foot")
+            || term_program == "iterm.app"
+        {
+            Protocol::Sixel
+        } else {
+            Protocol::HalfBlocks
+        }
+    }
+}
+
+// 图片在终端上的位置
+#[derive(PartialEq, Eq, Clone)]
+struct Placement {
+    key: String, // 封面缓存的key
+    area: Rect,  // 图片所在区域
+}
+
+pub struct CoverArt {
+    protocol: Protocol,
+    images: HashMap<String, Option<Rc<DynamicImage>>>, // 按专辑缓存解码后的封面
+    scaled: HashMap<String, (u32, u32, Rc<RgbImage>)>, // 按专辑缓存缩放到面板大小的封面
+    pending: Option<Placement>,                        // 本次绘制的图片位置
+    shown: Option<Placement>,                          // 已经输出到终端的图片位置
+}
+
+impl CoverArt {
+    // 无法获取终端像素大小时，假定的单元格像素大小
+    const DEFAULT_CELL_SIZE: (u32, u32) = (8, 16);
+
+    pub fn new() -> Self {
+        Self {
+            protocol: Protocol::detect(),
+            images: HashMap::new(),
+            scaled: HashMap::new(),
+            pending: None,
+            shown: None,
+        }
+    }
+
+    // 获取封面 ; 没有封面或解码失败时返回None
+    fn image(&mut self, media: &Media) -> Option<Rc<DynamicImage>> {
+        self.images
+            .entry(media.get_cover_key())
+            .or_insert_with(|| {
+                media
+                    .get_cover()
+                    .and_then(|data| image::load_from_memory(&data).ok())
+                    .map(Rc::new)
+            })
+            .clone()
+    }
+
+    // 是否有封面
+    pub fn has_cover(&mut self, media: &Media) -> bool {
+        self.image(media).is_some()
+    }
+
+    // 缩放封面 ; 保持宽高比
+    fn scale(&mut self, media: &Media, width: u32, height: u32) -> Option<Rc<RgbImage>> {
+        let key = media.get_cover_key();
+        if let Some((w, h, image)) = self.scaled.get(&key) {
+            if *w == width && *h == height {
+                return Some(image.clone());
+            }
+        }
+        let image = self.image(media)?;
+        let scaled = Rc::new(image.resize(width, height, FilterType::Triangle).to_rgb8());
+        self.scaled.insert(key, (width, height, scaled.clone()));
+        Some(scaled)
+    }
+
+    // 开始绘制新的一帧
+    pub fn begin_frame(&mut self) {
+        self.pending = None;
+    }
+
+    // 绘制封面
+    pub fn draw(&mut self, frame: &mut Frame, area: Rect, media: &Media) {
+        if area.width == 0 || area.height == 0 {
+            return;
+        }
+        match self.protocol {
+            Protocol::HalfBlocks => {
+                // 每个单元格上下两个像素
+                let width = area.width as u32;
+                let height = area.height as u32 * 2;
+                if let Some(image) = self.scale(media, width, height) {
+                    frame.render_widget(HalfBlocks(&image), area);
+                }
+            }
+            Protocol::Kitty | Protocol::Sixel => {
+                // 图片由终端绘制，这里只需要清空区域
+                frame.render_widget(Clear, area);
+                self.pending = Some(Placement {
+                    key: media.get_cover_key(),
+                    area,
+                });
+            }
+        }
+    }
+
+    // sixel图片覆盖在文字上，移除或移动图片时需要重绘整个终端
+    pub fn needs_repaint(&self) -> bool {
+        self.protocol == Protocol::Sixel && self.shown.is_some() && self.shown != self.pending
+    }
+
+    // 绘制完成后，输出图片协议
+    pub fn flush<W: Write>(&mut self, w: &mut W) -> std::io::Result<()> {
+        if self.shown == self.pending {
+            return Ok(());
+        }
+        if self.protocol == Protocol::Kitty && self.shown.is_some() {
+            // 删除之前输出的图片
+            write!(w, "\x1b_Ga=d,d=A,q=2\x1b\\")?;
+        }
+        if let Some(placement) = self.pending.clone() {
+            self.output(w, &placement)?;
+        }
+        w.flush()?;
+        self.shown = self.pending.clone();
+        Ok(())
+    }
+
+    fn output<W: Write>(&mut self, w: &mut W, placement: &Placement) -> std::io::Result<()> {
+        let Some(image) = self.scaled_for_placement(placement) else {
+            return Ok(());
+        };
+        let (cell_width, cell_height) = Self::cell_size();
+        let (width, height) = image.dimensions();
+        // 在区域内居中
+        let cols = width.div_ceil(cell_width) as u16;
+        let rows = height.div_ceil(cell_height) as u16;
+        let x = placement.area.x + placement.area.width.saturating_sub(cols) / 2;
+        let y = placement.area.y + placement.area.height.saturating_sub(rows) / 2;
+        queue!(w, MoveTo(x, y))?;
+        match self.protocol {
+            Protocol::Kitty => write_kitty(w, &image),
+            Protocol::Sixel => write_sixel(w, &image),
+            Protocol::HalfBlocks => Ok(()),
+        }
+    }
+
+    // 按区域的像素大小缩放封面
+    fn scaled_for_placement(&mut self, placement: &Placement) -> Option<Rc<RgbImage>> {
+        let (cell_width, cell_height) = Self::cell_size();
+        let width = placement.area.width as u32 * cell_width;
+        let height = placement.area.height as u32 * cell_height;
+        if let Some((w, h, image)) = self.scaled.get(&placement.key) {
+            if *w == width && *h == height {
+                return Some(image.clone());
+            }
+        }
+        let image = self.images.get(&placement.key).cloned().flatten()?;
+        let scaled = Rc::new(image.resize(width, height, FilterType::Triangle).to_rgb8());
+        self.scaled
+            .insert(placement.key.clone(), (width, height, scaled.clone()));
+        Some(scaled)
+    }
+
+    // 终端单元格的像素大小
+    fn cell_size() -> (u32, u32) {
+        match window_size() {
+            Ok(size) if size.width > 0 && size.height > 0 && size.columns > 0 && size.rows > 0 => (
+                (size.width / size.columns) as u32,
+                (size.height / size.rows) as u32,
+            ),
+            _ => Self::DEFAULT_CELL_SIZE,
+        }
+    }
+}
+
+// 使用半块字符绘制图片 ; 前景色为上半像素，背景色为下半像素
+struct HalfBlocks<'a>(&'a RgbImage);
+
+impl Widget for HalfBlocks<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let (width, height) = self.0.dimensions();
+        let cols = to_u16(width).min(area.width);
+        let rows = to_u16(height.div_ceil(2)).min(area.height);
+        let x0 = area.x + (area.width - cols) / 2;
+        let y0 = area.y + (area.height - rows) / 2;
+        for y in 0..rows {
+            for x in 0..cols {
+                let top = self.0.get_pixel(x as u32, y as u32 * 2);
+                let bottom = if y as u32 * 2 + 1 < height {
+                    let p = self.0.get_pixel(x as u32, y as u32 * 2 + 1);
+                    Color::Rgb(p[0], p[1], p[2])
+                } else {
+                    Color::Reset
+                };
+                buf.get_mut(x0 + x, y0 + y)
+                    .set_symbol("▀")
+                    .set_fg(Color::Rgb(top[0], top[1], top[2]))
+                    .set_bg(bottom);
+            }
+        }
+    }
+}
+
+fn to_u16(value: u32) -> u16 {
+    value.min(u16::MAX as u32) as u16
+}
+
+// kitty图片协议 ; 24位RGB数据，base64编码后按4096字节分块
+fn write_kitty<W: Write>(w: &mut W, image: &RgbImage) -> std::io::Result<()> {
+    let data = utils::base64(image.as_raw());
+    let chunks: Vec<&[u8]> = data.as_bytes().chunks(4096).collect();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = if i + 1 < chunks.len() { 1 } else { 0 };
+        if i == 0 {
+            write!(
+                w,
+                "\x1b_Ga=T,f=24,s={},v={},q=2,C=1,m={};",
+                image.width(),
+                image.height(),
+                more
+            )?;
+        } else {
+            write!(w, "\x1b_Gm={};", more)?;
+        }
+        w.write_all(chunk)?;
+        write!(w, "\x1b\\")?;
+    }
+    Ok(())
+}
+
+// sixel图片协议 ; 量化为6x6x6的调色板
+fn write_sixel<W: Write>(w: &mut W, image: &RgbImage) -> std::io::Result<()> {
+    let (width, height) = image.dimensions();
+    let level = |c: u8| (c as u32 * 5 + 127) / 255;
+    let indexes: Vec<u32> = image
+        .pixels()
+        .map(|p| level(p[0]) * 36 + level(p[1]) * 6 + level(p[2]))
+        .collect();
+
+    let mut out = String::new();
+    out.push_str(&format!("\x1bPq\"1;1;{};{}", width, height));
+    let mut used = [false; 216];
+    for &i in &indexes {
+        used[i as usize] = true;
+    }
+    for (i, _) in used.iter().enumerate().filter(|(_, &used)| used) {
+        let (r, g, b) = (i / 36, i / 6 % 6, i % 6);
+        out.push_str(&format!("#{};2;{};{};{}", i, r * 20, g * 20, b * 20));
+    }
+
+    for band in (0..height).step_by(6) {
+        let band_height = (height - band).min(6);
+        let mut colors: Vec<u32> = (band..band + band_height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| indexes[(y * width + x) as usize])
+            .collect();
+        colors.sort_unstable();
+        colors.dedup();
+        for color in colors {
+            out.push_str(&format!("#{}", color));
+            let mut run: Option<(char, u32)> = None;
+            for x in 0..width {
+                let mut bits = 0;
+                for dy in 0..band_height {
+                    if indexes[((band + dy) * width + x) as usize] == color {
+                        bits |= 1 << dy;
+                    }
+                }
+                let c = (63 + bits) as u8 as char;
+                run = match run {
+                    Some((last, n)) if last == c => Some((last, n + 1)),
+                    Some((last, n)) => {
+                        push_sixel_run(&mut out, last, n);
+                        Some((c, 1))
+                    }
+                    None => Some((c, 1)),
+                };
+            }
+            if let Some((last, n)) = run {
+                push_sixel_run(&mut out, last, n);
+            }
+            out.push('$');
+        }
+        out.push('-');
+    }
+    out.push_str("\x1b\\");
+    w.write_all(out.as_bytes())
+}
+
+fn push_sixel_run(out: &mut String, c: char, n: u32) {
+    if n > 3 {
+        out.push_str(&format!("!{}{}", n, c));
+    } else {
+        for _ in 0..n {
+            out.push(c);
+        }
+    }
+}
@@ -6,7 +6,10 @@ use ratatui::{
     Frame,
 };
 
-use crate::{config::Config, script::Script};
+use crate::{
+    config::Config,
+    script::{Script, SearchTarget},
+};
 
 use super::common::Common;
 
@@ -29,20 +32,32 @@ fn draw_fs_current_path(frame: &mut Frame, area: Rect, script: &mut Script) {
 }
 
 fn draw_fs_list(frame: &mut Frame, area: Rect, script: &mut Script) {
+    // 标题中展示排序方式
+    let title = format!("{} [{}]", Config::TITLE_EXPLORER, script.sort.title());
+    if script.search.is_active(SearchTarget::Explorer) {
+        let block = Common::block().title(Common::search_title(&title, &script.search));
+        let file_list = Common::search_list(&script.search).block(block);
+        frame.render_stateful_widget(file_list, area, &mut script.search.index);
+        return;
+    }
+
     let mut items = vec![];
+    let offset = if script.has_parent() { 1 } else { 0 };
     if script.has_parent() {
         items.push(draw_back())
     }
-    for it in &script.list {
+    for (i, it) in script.list.iter().enumerate() {
         let path = it.path();
+        // 标记或范围选择中的文件前加上记号
+        let marked = script.explorer_marked.contains(&path) || script.in_explorer_range(i + offset);
         if path.is_dir() {
-            items.push(draw_dir_item(it))
+            items.push(draw_dir_item(it, marked))
         } else if path.is_file() {
-            items.push(draw_file_item(it))
+            items.push(draw_file_item(it, marked))
         }
     }
 
-    let block = Common::block().title(Config::TITLE_EXPLORER);
+    let block = Common::block().title(title);
     let file_list = Common::list(items).block(block);
     frame.render_stateful_widget(file_list, area, &mut script.list_index);
 }
@@ -51,12 +66,20 @@ fn draw_back<'a>() -> ListItem<'a> {
     ListItem::new(Config::FILE_SYSTEM_BACK_SYMBOL)
 }
 
-fn draw_dir_item<'a>(entry: &DirEntry) -> ListItem<'a> {
+fn draw_dir_item<'a>(entry: &DirEntry, marked: bool) -> ListItem<'a> {
     let file_name = String::from(entry.file_name().to_str().unwrap()) + "/";
-    ListItem::new(file_name)
+    ListItem::new(mark(file_name, marked))
 }
 
-fn draw_file_item<'a>(entry: &DirEntry) -> ListItem<'a> {
+fn draw_file_item<'a>(entry: &DirEntry, marked: bool) -> ListItem<'a> {
     let file_name = String::from(entry.file_name().to_str().unwrap());
-    ListItem::new(file_name)
+    ListItem::new(mark(file_name, marked))
+}
+
+fn mark(name: String, marked: bool) -> String {
+    if marked {
+        format!("* {}", name)
+    } else {
+        name
+    }
 }
@@ -1,6 +1,6 @@
 use ratatui::{
     layout::{Alignment, Rect},
-    style::Stylize,
+    style::{Color, Stylize},
     text::Text,
     widgets::{Paragraph, Wrap},
     Frame,
@@ -22,3 +22,18 @@ pub fn draw_header(frame: &mut Frame, area: Rect, text: &str) {
 
     frame.render_widget(msg_p, area);
 }
+
+// 错误信息
+pub fn draw_error(frame: &mut Frame, area: Rect, text: &str) {
+    let block = Common::block()
+        .title(Config::TITLE_SOFTWARE)
+        .title_alignment(Alignment::Left)
+        .border_style(Color::Red);
+    let msg_p = Paragraph::new(Text::from(text.to_string()))
+        .fg(Color::Red)
+        .alignment(Alignment::Center)
+        .block(block)
+        .wrap(Wrap { trim: true });
+
+    frame.render_widget(msg_p, area);
+}
@@ -0,0 +1,173 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::Paragraph,
+    Frame,
+};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::{
+    config::Config,
+    script::{LyricMode, Script},
+};
+
+use super::{
+    common::Common,
+    media::{draw_control, draw_header},
+};
+
+// 歌词界面 ; 歌词居中，正在唱的一行加粗，其他行变暗
+pub fn draw_karaoke(frame: &mut Frame, area: Rect, script: &mut Script) {
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(0),
+            Constraint::Length(3),
+        ])
+        .split(area);
+    draw_header(frame, layout[0], script);
+    draw_lyrics(frame, layout[1], script);
+    draw_control(frame, layout[2], script);
+}
+
+fn draw_lyrics(frame: &mut Frame, area: Rect, script: &mut Script) {
+    let block = Common::block().title(Config::TITLE_LYRICS);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let width = inner.width as usize;
+    let mut rows: Vec<Line> = vec![];
+    let mut center = 0.0;
+
+    if let Some(first) = script.current() {
+        let lyrics = first.media.get_lyrics();
+        let (position, total) = first.progress();
+
+        if !lyrics.list.is_empty() {
+            let position = lyrics.position(position);
+            let current = lyrics.current_index(position);
+            // 每行歌词的中心所在的行
+            let mut centers = vec![];
+            for (i, it) in lyrics.list.iter().enumerate() {
+                if i > 0 {
+                    rows.push(Line::default());
+                }
+                let start = rows.len();
+                let distance = match current {
+                    Some(current) => current.abs_diff(i),
+                    None => i + 1,
+                };
+                let style = match distance {
+                    0 => Style::default()
+                        .fg(Common::LIGHT_THEME)
+                        .add_modifier(Modifier::BOLD),
+                    1 => Style::default().add_modifier(Modifier::DIM),
+                    _ => Style::default().fg(Color::DarkGray),
+                };
+
+                let (content, sung_len) = match (script.lyric_mode, &it.translation) {
+                    (LyricMode::Translation, Some(translation)) => (translation.as_str(), 0),
+                    _ if distance == 0 && !it.words.is_empty() => {
+                        (it.content.as_str(), it.sung_len(position))
+                    }
+                    _ => (it.content.as_str(), 0),
+                };
+                for (s, e) in wrap(content, width) {
+                    // 已唱部分高亮
+                    let sung_end = sung_len.clamp(s, e);
+                    rows.push(Line::from(vec![
+                        Span::styled(
+                            content[s..sung_end].to_string(),
+                            style.fg(Common::LYRIC_SUNG),
+                        ),
+                        Span::styled(content[sung_end..e].to_string(), style),
+                    ]));
+                }
+                // 翻译在原文下方
+                if let (LyricMode::Both, Some(translation)) = (script.lyric_mode, &it.translation) {
+                    let style = style
+                        .remove_modifier(Modifier::BOLD)
+                        .add_modifier(Modifier::ITALIC);
+                    for (s, e) in wrap(translation, width) {
+                        rows.push(Line::styled(translation[s..e].to_string(), style));
+                    }
+                }
+                centers.push((start + rows.len() - 1) as f64 / 2.0);
+            }
+
+            // 在下一行开始前平滑滚动
+            let index = current.unwrap_or(0);
+            center = centers[index];
+            let next_index = if current.is_some() { index + 1 } else { index };
+            if let (Some(next), Some(next_center)) =
+                (lyrics.list.get(next_index), centers.get(next_index))
+            {
+                let position = position + Config::KARAOKE_SCROLL_DURATION;
+                if position > next.time {
+                    let t = (position - next.time).as_secs_f64()
+                        / Config::KARAOKE_SCROLL_DURATION.as_secs_f64();
+                    center += (next_center - center) * t.min(1.0);
+                }
+            }
+        } else if !lyrics.unsynced.is_empty() {
+            // 纯文本歌词 ; 按播放进度滚动
+            for it in lyrics.unsynced.iter() {
+                for (s, e) in wrap(it, width) {
+                    rows.push(Line::from(it[s..e].to_string()));
+                }
+            }
+            if !total.is_zero() {
+                let ratio = position.as_secs_f64() / total.as_secs_f64();
+                center = ratio.min(1.0) * rows.len() as f64;
+            }
+        }
+    }
+
+    if rows.is_empty() {
+        rows.push(Line::from(Config::TIP_NO_LYRICS));
+    }
+
+    // 以center为中心截取可见的行
+    let height = inner.height as usize;
+    let top = center.round() as isize - (height / 2) as isize;
+    let lines: Vec<Line> = (top..top + height as isize)
+        .map(|i| {
+            usize::try_from(i)
+                .ok()
+                .and_then(|i| rows.get(i).cloned())
+                .unwrap_or_default()
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines).alignment(Alignment::Center), inner);
+}
+
+// 按显示宽度换行 ; 返回每行在原文中的字节范围，优先在空格处断开
+fn wrap(text: &str, width: usize) -> Vec<(usize, usize)> {
+    let text = text.trim_end();
+    let mut rows = vec![];
+    let mut start = 0;
+    let mut line_width = 0;
+    let mut last_space = None;
+    for (i, c) in text.char_indices() {
+        let w = c.width().unwrap_or(0);
+        if width > 0 && line_width + w > width && i > start {
+            let end = match last_space {
+                Some(space) if space > start => space,
+                _ => i,
+            };
+            rows.push((start, end));
+            // 下一行不以空格开头
+            start = end + text[end..i].len() - text[end..i].trim_start().len();
+            line_width = text[start..i].width();
+            last_space = None;
+        }
+        if c == ' ' {
+            last_space = Some(i);
+        }
+        line_width += w;
+    }
+    rows.push((start, text.len()));
+    rows
+}
@@ -5,13 +5,19 @@ use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     symbols,
+    text::{Line, Span, Text},
     widgets::{Bar, BarChart, BarGroup, LineGauge, List, ListItem, ListState, Padding, Paragraph},
     Frame,
 };
 
-use crate::{config::Config, script::Script};
+use crate::{
+    config::Config,
+    media::Media,
+    script::{LyricMode, Script, SearchTarget, TagEditor},
+    utils,
+};
 
-use super::common::Common;
+use super::{common::Common, cover::CoverArt};
 
 pub fn draw_media(
     frame: &mut Frame,
@@ -20,6 +26,7 @@ pub fn draw_media(
     lyrics_width: u16,
     info_width: u16,
     show_info: bool,
+    cover: &mut CoverArt,
 ) {
     let media_layout = Layout::default()
         .direction(Direction::Vertical)
@@ -37,11 +44,12 @@ pub fn draw_media(
         lyrics_width,
         info_width,
         show_info,
+        cover,
     );
     draw_control(frame, media_layout[2], script);
 }
 
-fn draw_header(frame: &mut Frame, area: Rect, script: &mut Script) {
+pub fn draw_header(frame: &mut Frame, area: Rect, script: &mut Script) {
     let header_layout = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
@@ -69,6 +77,21 @@ fn draw_header(frame: &mut Frame, area: Rect, script: &mut Script) {
     frame.render_widget(next_playing, header_layout[1]);
 }
 
+// 已唱部分高亮
+fn karaoke_line<'a>(content: &str, sung_len: usize) -> Line<'a> {
+    let content = content.trim_end();
+    let (sung, rest) = content.split_at(cmp::min(sung_len, content.len()));
+    Line::from(vec![
+        Span::styled(
+            sung.to_string(),
+            Style::default()
+                .fg(Common::LYRIC_SUNG)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(rest.to_string()),
+    ])
+}
+
 fn draw_stage(
     frame: &mut Frame,
     area: Rect,
@@ -76,6 +99,7 @@ fn draw_stage(
     lyrics_width: u16,
     info_width: u16,
     show_info: bool,
+    cover: &mut CoverArt,
 ) {
     let mut wave_chart = BarChart::default().block(Common::block().title(Config::TITLE_WAVE));
 
@@ -85,18 +109,67 @@ fn draw_stage(
 
     let mut tag_widget_length = 0;
     let mut tag_widget = List::default();
+    let mut tag_index = ListState::default();
+    let mut has_cover = false;
 
-    if let Some(first) = script.play_list.first() {
+    if let Some(first) = script.current() {
         // lyrics
         let lyrics = first.media.get_lyrics();
         let length = lyrics.list.len();
-        if length > 0 {
+        if let Some(sync) = &script.lyric_sync {
+            // 同步模式 ; 展示每行已标记的时间
+            let items: Vec<ListItem> = sync
+                .lines
+                .iter()
+                .zip(sync.times.iter())
+                .map(|(line, time)| {
+                    let stamp = match time {
+                        Some(time) => Media::format_lyric_time(*time),
+                        None => "[--:--.--]".to_string(),
+                    };
+                    ListItem::new(format!("{} {}", stamp, line))
+                })
+                .collect();
+            lyric_index = sync.index.clone();
+            lyric_widget_length = lyrics_width;
+            // 切换歌曲后继续同步原来的歌曲 ; 标题中展示同步的文件
+            let title = if *first.media.get_path() == sync.path {
+                Config::TITLE_LYRICS_SYNC.to_string()
+            } else {
+                let name = sync.path.file_name().unwrap_or_default().to_string_lossy();
+                format!("{} {}", Config::TITLE_LYRICS_SYNC, name)
+            };
+            lyric_widget = Common::list(items).block(Common::block().title(title));
+        } else if length == 0 && !lyrics.unsynced.is_empty() {
+            // 纯文本歌词 ; 按播放进度滚动
+            let (current_pos, total) = first.progress();
+            let lines = (area.height as usize).saturating_sub(2);
+            let scroll = lyrics.unsynced.len().saturating_sub(lines);
+            let offset = if total.is_zero() {
+                0
+            } else {
+                let ratio = current_pos.as_secs_f64() / total.as_secs_f64();
+                (ratio.min(1.0) * scroll as f64) as usize
+            };
+            let items: Vec<ListItem> = lyrics
+                .unsynced
+                .iter()
+                .map(|it| ListItem::new(it.clone()))
+                .collect();
+            lyric_index = ListState::default().with_offset(offset);
+            lyric_widget_length = lyrics_width;
+            lyric_widget = Common::list(items).block(Common::block().title(Config::TITLE_LYRICS));
+        } else if length > 0 {
             let mut items = vec![];
             let (current_pos, _) = first.progress();
+            let current_pos = lyrics.position(current_pos);
 
+            // 同时展示原文和翻译时，每行歌词占两行
+            let both = script.lyric_mode == LyricMode::Both && lyrics.has_translation();
+            let item_height = if both { 2 } else { 1 };
             let (top_num, bottom_num) = {
                 // border-top and border-bottom is 2
-                let lines = (area.height as usize).checked_sub(2).unwrap_or(0);
+                let lines = (area.height as usize).saturating_sub(2) / item_height;
                 let half = lines / 2;
                 if lines % 2 == 0 {
                     (half, half)
@@ -105,26 +178,63 @@ fn draw_stage(
                 }
             };
 
-            let mid_index = match lyrics
-                .list
-                .iter()
-                .enumerate()
-                .find(|&(_, it)| it.time >= current_pos)
-            {
-                Some((value, _)) => value,
-                None => length,
+            // 有歌词光标时以光标为中心
+            let cursor = script.lyric_cursor().filter(|&it| it < length);
+            let mid_index = match cursor {
+                Some(cursor) => cursor + 1,
+                None => lyrics
+                    .list
+                    .iter()
+                    .position(|it| it.time >= current_pos)
+                    .unwrap_or(length),
             };
 
             let end_index = cmp::min(mid_index + bottom_num, length - 1);
-            let start_index = mid_index.checked_sub(top_num).unwrap_or(0);
-            let selected_index = mid_index.checked_sub(start_index + 1).unwrap_or(0);
+            let start_index = mid_index.saturating_sub(top_num);
+            let selected_index = mid_index.saturating_sub(start_index + 1);
 
             lyric_index.select(Some(selected_index));
-            for it in &lyrics.list[start_index..=end_index] {
-                items.push(ListItem::new(it.content.clone()))
+            for (i, it) in lyrics.list[start_index..=end_index].iter().enumerate() {
+                // 正在唱的一行按逐字时间高亮
+                let original = if i == selected_index
+                    && mid_index > 0
+                    && cursor.is_none()
+                    && !it.words.is_empty()
+                {
+                    karaoke_line(&it.content, it.sung_len(current_pos))
+                } else {
+                    Line::from(it.content.clone())
+                };
+                let text = match (script.lyric_mode, it.translation.clone()) {
+                    // 翻译在原文下方，颜色较暗
+                    (LyricMode::Both, Some(translation)) => Text::from(vec![
+                        original,
+                        Line::styled(translation, Style::default().add_modifier(Modifier::DIM)),
+                    ]),
+                    (LyricMode::Both, None) if both => Text::from(vec![original, Line::default()]),
+                    (LyricMode::Translation, Some(translation)) => Text::from(translation),
+                    _ => Text::from(original),
+                };
+                items.push(ListItem::new(text))
             }
             lyric_widget_length = lyrics_width;
-            lyric_widget = Common::list(items).block(Common::block().title(Config::TITLE_LYRICS));
+            // 标题中展示未保存的偏移
+            let title = if lyrics.adjust == 0 {
+                Config::TITLE_LYRICS.to_string()
+            } else {
+                format!("{} ({:+}ms)", Config::TITLE_LYRICS, lyrics.adjust)
+            };
+            let title = if script.search.is_active(SearchTarget::Lyrics) {
+                Common::search_title(&title, &script.search)
+            } else {
+                title
+            };
+            // 获得焦点时高亮边框
+            let mut block = Common::block().title(title);
+            if script.lyric_focus {
+                block = block.border_style(Style::default().fg(Common::LIGHT_THEME));
+            }
+            lyric_widget = Common::list(items).block(block);
         }
         // wave
         if first.is_playing() {
@@ -152,7 +262,11 @@ fn draw_stage(
         }
         // mp3 info
         let tags = first.media.get_id3_tag();
-        if tags.len() > 0 && show_info {
+        has_cover = show_info && cover.has_cover(&first.media);
+        if has_cover {
+            tag_widget_length = info_width;
+        }
+        if show_info {
             let mut items = vec![];
             for tag in tags.iter() {
                 let text = if tag.0 == "COMM" {
@@ -162,9 +276,64 @@ fn draw_stage(
                 };
                 items.push(ListItem::new(text))
             }
+            // 播放统计展示在标签后面
+            let stats = script
+                .stats
+                .get(first.media.get_path())
+                .cloned()
+                .unwrap_or_default();
+            let rating = match stats.rating {
+                Some(rating) => format!(
+                    "{}{}",
+                    "★".repeat(rating as usize),
+                    "☆".repeat(5 - rating as usize)
+                ),
+                None => Config::EMPTY.to_string(),
+            };
+            let favorite = if stats.favorite { "♥" } else { Config::EMPTY };
+            let last_played = match stats.last_played {
+                Some(secs) => utils::format_date(secs),
+                None => Config::EMPTY.to_string(),
+            };
+            let values = [
+                stats.plays.to_string(),
+                stats.skips.to_string(),
+                rating,
+                favorite.to_string(),
+                last_played,
+            ];
+            for (name, value) in Config::STATS_NAMES.iter().zip(values) {
+                items.push(ListItem::new(format!("{}: {}", name, value)));
+            }
             tag_widget_length = info_width;
             tag_widget = Common::list(items).block(Common::block().title(Config::TITLE_MEIDA_INFO));
         }
+        // 编辑标签 ; 替换信息组件
+        if let Some(editor) = &script.tag_editor {
+            let mut items = vec![];
+            for (i, (field, value)) in editor.fields.iter().enumerate() {
+                let value = if editor.editing && Some(i) == editor.index.selected() {
+                    format!("{}_", editor.input)
+                } else {
+                    TagEditor::display(value)
+                };
+                let changed = if editor.is_changed(i) { "*" } else { "" };
+                items.push(ListItem::new(format!(
+                    "{}{}: {}",
+                    changed,
+                    field.name(),
+                    value
+                )));
+            }
+            let title = format!("{}({})", Config::TITLE_TAG_EDITOR, editor.targets.len());
+            tag_widget_length = info_width;
+            tag_widget = Common::list(items).block(
+                Common::block()
+                    .title(title)
+                    .border_style(Style::default().fg(Common::LIGHT_THEME)),
+            );
+            tag_index = editor.index.clone();
+        }
     }
 
     let layout = Layout::default()
@@ -178,10 +347,25 @@ fn draw_stage(
 
     frame.render_stateful_widget(lyric_widget, layout[0], &mut lyric_index);
     frame.render_widget(wave_chart, layout[1]);
-    frame.render_widget(tag_widget, layout[2]);
+
+    // 封面在信息组件上方
+    if let (true, Some(first)) = (has_cover, script.current()) {
+        // 半块字符绘制时，正方形封面的高度为宽度的一半 ; border is 2
+        let cover_height = cmp::min(info_width / 2 + 1, layout[2].height / 2);
+        let info_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(cover_height), Constraint::Min(0)])
+            .split(layout[2]);
+        let block = Common::block().title(Config::TITLE_COVER);
+        cover.draw(frame, block.inner(info_layout[0]), &first.media);
+        frame.render_widget(block, info_layout[0]);
+        frame.render_stateful_widget(tag_widget, info_layout[1], &mut tag_index);
+    } else {
+        frame.render_stateful_widget(tag_widget, layout[2], &mut tag_index);
+    }
 }
 
-fn draw_control(frame: &mut Frame, area: Rect, script: &mut Script) {
+pub fn draw_control(frame: &mut Frame, area: Rect, script: &mut Script) {
     let control_layout = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Max(25), Constraint::Min(0)])
@@ -192,7 +376,7 @@ fn draw_control(frame: &mut Frame, area: Rect, script: &mut Script) {
 
 fn draw_volume(frame: &mut Frame, area: Rect, script: &mut Script) {
     let volume = script.volume;
-    let label = if let Some(first) = script.play_list.first() {
+    let label = if let Some(first) = script.current() {
         if first.is_playing() {
             "||"
         } else {
@@ -217,7 +401,7 @@ fn draw_volume(frame: &mut Frame, area: Rect, script: &mut Script) {
 }
 
 fn draw_progress(frame: &mut Frame, area: Rect, script: &mut Script) {
-    let (current, total) = if let Some(first) = script.play_list.first() {
+    let (current, total) = if let Some(first) = script.current() {
         first.progress()
     } else {
         (Duration::default(), Duration::default())
@@ -1,45 +1,83 @@
+use std::io::Write;
+
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers,
+        MouseButton, MouseEvent, MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
     backend::{Backend, CrosstermBackend},
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     Terminal,
 };
 
-use crate::{config::Config, script::Script};
+use crate::{
+    config::Config,
+    events::{AppEvent, Events},
+    script::{EnqueueMode, PlaylistInput, Script, SearchTarget},
+};
 
-use self::{fs::draw_fs, header::draw_header, media::draw_media, play_list::draw_play_list};
+use self::{
+    cover::CoverArt,
+    fs::draw_fs,
+    header::{draw_error, draw_header},
+    karaoke::draw_karaoke,
+    media::draw_media,
+    play_list::draw_play_list,
+    playlists::{draw_playlist_picker, draw_playlists},
+};
 
 mod common;
+mod cover;
 mod fs;
 mod header;
+mod karaoke;
 mod media;
 mod play_list;
+mod playlists;
 
 // Tab切换
 #[derive(PartialEq, Eq)]
 enum TabMode {
-    Explorer, // 本地文件夹
-    PlayList, // 播放列表
+    Explorer,  // 本地文件夹
+    PlayList,  // 播放列表
+    Playlists, // 播放列表管理
 }
 
 pub struct UI {
     script: Script,
+    events: Events,    // 输入、播放、后台任务的事件
     full_screen: bool, // 是否全屏展示
+    karaoke: bool,     // 是否展示歌词界面
     show_info: bool,   // 是否展示media tag
     tab: TabMode,
+    cover: CoverArt,             // 专辑封面
+    count: usize,                // 数字前缀 ; 移动的距离
+    play_list_area: Rect,        // 播放列表的位置 ; 鼠标拖动
+    drag: Option<(usize, bool)>, // 正在拖动的歌曲和是否已经移动
 }
 
 impl UI {
-    pub fn new(script: Script, full_screen: bool, show_info: bool) -> Result<(), std::io::Error> {
+    pub fn new(
+        script: Script,
+        events: Events,
+        full_screen: bool,
+        show_info: bool,
+    ) -> Result<(), std::io::Error> {
         let mut this = Self {
             script,
+            events,
             full_screen,
+            karaoke: false,
             show_info,
             tab: TabMode::Explorer,
+            cover: CoverArt::new(),
+            count: 0,
+            play_list_area: Rect::default(),
+            drag: None,
         };
 
         // 初始化终端
@@ -51,6 +89,7 @@ impl UI {
         terminal.clear()?;
 
         // 刷新界面
+        this.events.listen_input();
         this.refresh(&mut terminal)?;
 
         // 恢复终端
@@ -62,56 +101,195 @@ impl UI {
         )?;
         terminal.show_cursor()?;
 
+        // 保存正在使用的播放列表和播放统计
+        this.script.save_active_playlist()?;
+        this.script.stats.save()?;
+
         Ok(())
     }
 
     fn refresh<B>(&mut self, terminal: &mut Terminal<B>) -> Result<(), std::io::Error>
     where
-        B: Backend,
+        B: Backend + Write,
     {
+        let mut redraw = true;
         loop {
             // 初始化脚本
             self.script.init();
-            // 绘制UI
-            self.draw(terminal)?;
-            std::thread::sleep(Config::REFRESH_RATE);
-            // 处理事件
-            if crossterm::event::poll(Config::REFRESH_RATE)? {
-                if let Event::Key(key) = event::read()? {
+            // 绘制UI ; 状态没有变化时不重绘
+            if redraw {
+                self.draw(terminal)?;
+                if self.cover.needs_repaint() {
+                    terminal.clear()?;
+                    self.draw(terminal)?;
+                }
+                self.cover.flush(terminal.backend_mut())?;
+            }
+            // 等待事件 ; 播放中定时刷新
+            redraw = true;
+            match self.events.next(self.script.tick_rate()) {
+                AppEvent::Input(Event::Key(key)) => {
+                    // 是否可以操作歌词
+                    let can_operator_lyrics = !self.karaoke && self.script.lyric_focus;
                     // 是否可以操作目录列表
-                    let can_operator_explorer = !self.full_screen && self.tab == TabMode::Explorer;
+                    let can_list = !self.full_screen && !self.karaoke && !can_operator_lyrics;
+                    let can_operator_explorer = can_list && self.tab == TabMode::Explorer;
                     // 是否可以操作播放列表
-                    let can_operator_play_list = !self.full_screen && self.tab == TabMode::PlayList;
-                    if key.kind == KeyEventKind::Press {
+                    let can_operator_play_list = can_list && self.tab == TabMode::PlayList;
+                    // 是否可以操作播放列表管理
+                    let can_operator_playlists = can_list && self.tab == TabMode::Playlists;
+                    // 当前可以搜索的列表
+                    let search_target = match self.tab {
+                        _ if can_operator_lyrics => SearchTarget::Lyrics,
+                        TabMode::Explorer => SearchTarget::Explorer,
+                        TabMode::PlayList | TabMode::Playlists => SearchTarget::PlayList,
+                    };
+                    let is_searching = (can_list || can_operator_lyrics)
+                        && self.script.search.is_active(search_target);
+                    let tag_editing = self.script.tag_editor.as_ref().map(|it| it.editing);
+                    if key.kind == KeyEventKind::Press && self.script.playlists.picker.is_some() {
+                        // 选择要添加到的播放列表
+                        match key.code {
+                            KeyCode::Up => self.script.on_picker_up(),
+                            KeyCode::Down => self.script.on_picker_down(),
+                            KeyCode::Enter => self.script.on_picker_confirm(),
+                            KeyCode::Esc => self.script.on_picker_cancel(),
+                            _ => {}
+                        }
+                    } else if key.kind == KeyEventKind::Press
+                        && self.script.playlists.input.is_some()
+                    {
+                        // 输入播放列表名称
+                        match key.code {
+                            KeyCode::Char(c) => self.script.on_playlist_input(c),
+                            KeyCode::Backspace => self.script.on_playlist_input_backspace(),
+                            KeyCode::Enter => self.script.on_playlist_input_confirm(),
+                            KeyCode::Esc => self.script.on_playlist_input_cancel(),
+                            _ => {}
+                        }
+                    } else if key.kind == KeyEventKind::Press && tag_editing == Some(true) {
+                        // 输入标签内容
+                        match key.code {
+                            KeyCode::Char(c) => self.script.on_tag_edit_input(c),
+                            KeyCode::Backspace => self.script.on_tag_edit_backspace(),
+                            KeyCode::Enter => self.script.on_tag_edit_enter(),
+                            KeyCode::Esc => self.script.on_tag_edit_cancel(),
+                            _ => {}
+                        }
+                    } else if key.kind == KeyEventKind::Press && tag_editing == Some(false) {
+                        // 选择要编辑的标签
+                        match key.code {
+                            KeyCode::Up => self.script.on_tag_edit_up(),
+                            KeyCode::Down => self.script.on_tag_edit_down(),
+                            KeyCode::Enter => self.script.on_tag_edit_enter(),
+                            KeyCode::Char('s' | 'S') => self.script.on_tag_edit_save(),
+                            KeyCode::Esc => self.script.on_tag_edit_cancel(),
+                            KeyCode::Char(' ') => self.script.on_change_pause_play(),
+                            _ => {}
+                        }
+                    } else if key.kind == KeyEventKind::Press && self.script.search.editing {
+                        // 输入搜索内容
                         match key.code {
+                            KeyCode::Char(c) => self.script.on_search_input(c),
+                            KeyCode::Backspace => self.script.on_search_backspace(),
+                            KeyCode::Enter => self.script.on_search_confirm(),
+                            KeyCode::Esc => self.script.on_search_cancel(),
+                            KeyCode::Up => self.script.on_search_prev(),
+                            KeyCode::Down => self.script.on_search_next(),
+                            _ => {}
+                        }
+                    } else if key.kind == KeyEventKind::Press && self.script.lyric_sync.is_some() {
+                        // 同步纯文本歌词
+                        match key.code {
+                            KeyCode::Enter => self.script.on_lyric_sync_tap(),
+                            KeyCode::Up => self.script.on_lyric_sync_up(),
+                            KeyCode::Down => self.script.on_lyric_sync_down(),
+                            KeyCode::Char(']') => {
+                                self.script.on_lyric_sync_nudge(Config::LYRIC_OFFSET_STEP)
+                            }
+                            KeyCode::Char('[') => {
+                                self.script.on_lyric_sync_nudge(-Config::LYRIC_OFFSET_STEP)
+                            }
+                            KeyCode::Char('s' | 'S') => self.script.on_lyric_sync_save(),
+                            KeyCode::Esc => self.script.on_lyric_sync_cancel(),
+                            KeyCode::Char(' ') => self.script.on_change_pause_play(),
+                            KeyCode::Left => self.script.on_reverse(),
+                            KeyCode::Right => self.script.on_forward(),
+                            _ => {}
+                        }
+                    } else if key.kind == KeyEventKind::Press {
+                        // 数字前缀只对下一个按键有效
+                        let count = std::mem::take(&mut self.count);
+                        let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+                        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+                        let alt = key.modifiers.contains(KeyModifiers::ALT);
+                        match key.code {
+                            // 撤销、重做播放列表的修改
+                            KeyCode::Char('z' | 'Z') if ctrl => self.script.on_undo(),
+                            KeyCode::Char('y' | 'Y') if ctrl => self.script.on_redo(),
+                            // 替换播放列表 ; 使用Ctrl避免误触，可以撤销
+                            KeyCode::Char('r' | 'R') if ctrl && can_operator_explorer => {
+                                self.script.on_enqueue(EnqueueMode::Replace)
+                            }
+                            // 给正在播放的歌曲评分 ; Alt+0 清除评分
+                            KeyCode::Char('0') if alt => self.script.on_set_rating(None),
+                            KeyCode::Char(c @ '1'..='5') if alt => {
+                                self.script.on_set_rating(Some(c as u8 - b'0'))
+                            }
+                            // 收藏正在播放的歌曲
+                            KeyCode::Char('*') => self.script.on_toggle_favorite(),
                             // 关闭应用
                             KeyCode::Char('q') | KeyCode::Char('Q') => break,
+                            // 移动选中的歌曲 ; 可以使用数字前缀指定距离
+                            KeyCode::Char(c @ '0'..='9') if can_operator_play_list => {
+                                self.count = count * 10 + c as usize - '0' as usize;
+                            }
+                            KeyCode::Up if shift && can_operator_play_list => {
+                                self.script.on_move_play_list(-(count.max(1) as isize))
+                            }
+                            KeyCode::Down if shift && can_operator_play_list => {
+                                self.script.on_move_play_list(count.max(1) as isize)
+                            }
                             // 列表操作
                             KeyCode::Up => {
-                                if can_operator_explorer {
+                                if can_operator_lyrics {
+                                    self.script.on_lyric_up()
+                                } else if can_operator_explorer {
                                     self.script.on_list_up()
                                 } else if can_operator_play_list {
                                     self.script.on_play_list_up()
+                                } else if can_operator_playlists {
+                                    self.script.on_playlists_up()
                                 }
                             }
                             KeyCode::Down => {
-                                if can_operator_explorer {
+                                if can_operator_lyrics {
+                                    self.script.on_lyric_down()
+                                } else if can_operator_explorer {
                                     self.script.on_list_down()
                                 } else if can_operator_play_list {
                                     self.script.on_play_list_down()
+                                } else if can_operator_playlists {
+                                    self.script.on_playlists_down()
                                 }
                             }
                             KeyCode::Enter => {
-                                if can_operator_explorer {
+                                if can_operator_lyrics {
+                                    self.script.on_lyric_seek()
+                                } else if can_operator_explorer {
                                     self.script.on_add_emdia_to_list()
                                 } else if can_operator_play_list {
                                     self.script.on_play_now_play_list()
+                                } else if can_operator_playlists {
+                                    self.script.on_switch_playlist()
                                 }
                             }
                             // 删除按钮
                             KeyCode::Delete => {
                                 if can_operator_play_list {
                                     self.script.on_remove_from_play_list();
+                                } else if can_operator_playlists {
+                                    self.script.on_delete_playlist();
                                 }
                             }
                             // 回退按钮
@@ -126,23 +304,77 @@ impl UI {
                             KeyCode::Char('i' | 'I') => {
                                 self.show_info = !self.show_info;
                             }
+                            // 编辑标签
+                            KeyCode::Char('e' | 'E') => self.script.on_tag_edit_start(),
+                            // 切换歌词翻译
+                            KeyCode::Char('l' | 'L') => self.script.on_change_lyric_mode(),
+                            // 调整歌词偏移 ; ]提前 [推迟
+                            KeyCode::Char(']') => {
+                                self.script.on_lyric_offset(Config::LYRIC_OFFSET_STEP)
+                            }
+                            KeyCode::Char('[') => {
+                                self.script.on_lyric_offset(-Config::LYRIC_OFFSET_STEP)
+                            }
+                            KeyCode::Char('o' | 'O') => self.script.on_save_lyric_offset(),
+                            // 同步纯文本歌词
+                            KeyCode::Char('y' | 'Y') => self.script.on_lyric_sync_start(),
+                            // 歌词面板焦点
+                            KeyCode::Char('g' | 'G') => self.script.on_lyric_focus(),
                             // 全屏播放 ; F11与大多数终端冲突了
                             KeyCode::Char('f' | 'F') => {
                                 self.full_screen = !self.full_screen;
                             }
+                            // 歌词界面
+                            KeyCode::Char('k' | 'K') => {
+                                self.karaoke = !self.karaoke;
+                            }
                             KeyCode::Esc => {
-                                if self.full_screen {
+                                if self.karaoke {
+                                    self.karaoke = false;
+                                } else if is_searching {
+                                    self.script.on_search_cancel();
+                                } else if self.full_screen {
                                     self.full_screen = false;
+                                } else if self.script.lyric_focus {
+                                    self.script.on_lyric_focus();
+                                } else if can_operator_play_list
+                                    && self.script.has_play_list_selection()
+                                {
+                                    self.script.on_clear_play_list_selection();
+                                } else if can_operator_explorer
+                                    && self.script.has_explorer_selection()
+                                {
+                                    self.script.on_clear_explorer_selection();
+                                }
+                            }
+                            // 搜索
+                            KeyCode::Char('/') => {
+                                if can_operator_explorer
+                                    || can_operator_play_list
+                                    || can_operator_lyrics
+                                {
+                                    self.script.on_search_start(search_target);
                                 }
                             }
+                            // 跳转到上一个或下一个匹配项
+                            KeyCode::Char('n') if is_searching => self.script.on_search_next(),
+                            KeyCode::Char('N') if is_searching => self.script.on_search_prev(),
                             // 切换功能
-                            KeyCode::Tab => match self.tab {
-                                TabMode::Explorer => {
-                                    self.tab = TabMode::PlayList;
-                                    self.script.play_list_index.select(Some(0));
+                            KeyCode::Tab => {
+                                self.script.search.clear();
+                                match self.tab {
+                                    TabMode::Explorer => {
+                                        self.tab = TabMode::PlayList;
+                                        let current = self.script.current.unwrap_or(0);
+                                        self.script.play_list_index.select(Some(current));
+                                    }
+                                    TabMode::PlayList => {
+                                        self.tab = TabMode::Playlists;
+                                        self.script.on_playlists_open();
+                                    }
+                                    TabMode::Playlists => self.tab = TabMode::Explorer,
                                 }
-                                TabMode::PlayList => self.tab = TabMode::Explorer,
-                            },
+                            }
                             // 媒体播放
                             KeyCode::Media(media_key_code) => match media_key_code {
                                 event::MediaKeyCode::Play => self.script.on_play(),
@@ -171,23 +403,140 @@ impl UI {
                                     self.script.on_post_top_play_list();
                                 }
                             }
+                            // 添加文件夹 ; 追加、下一首播放
+                            KeyCode::Char('a' | 'A') if can_operator_explorer => {
+                                self.script.on_enqueue(EnqueueMode::Append)
+                            }
+                            KeyCode::Char('p' | 'P') if can_operator_explorer => {
+                                self.script.on_enqueue(EnqueueMode::PlayNext)
+                            }
+                            // 下一首播放
+                            KeyCode::Char('p' | 'P') if can_operator_play_list => {
+                                self.script.on_play_next_play_list()
+                            }
+                            KeyCode::Char('x' | 'X') if can_operator_play_list => {
+                                self.script.on_clear_up_next()
+                            }
+                            // 播放列表管理
+                            KeyCode::Char('a' | 'A') if can_operator_playlists => {
+                                self.script.on_playlist_input_start(PlaylistInput::Create)
+                            }
+                            KeyCode::Char('r' | 'R') if can_operator_playlists => {
+                                self.script.on_playlist_input_start(PlaylistInput::Rename)
+                            }
+                            KeyCode::Char('d' | 'D') if can_operator_playlists => self
+                                .script
+                                .on_playlist_input_start(PlaylistInput::Duplicate),
+                            KeyCode::Char('s' | 'S') if can_operator_playlists => {
+                                self.script.on_playlist_input_start(PlaylistInput::Smart)
+                            }
+                            KeyCode::Char('u' | 'U') if can_operator_playlists => {
+                                self.script.on_playlist_input_start(PlaylistInput::Query)
+                            }
+                            // 文件夹排序
+                            KeyCode::Char('s' | 'S') if can_operator_explorer => {
+                                self.script.on_next_sort_mode()
+                            }
+                            KeyCode::Char('r' | 'R') if can_operator_explorer => {
+                                self.script.on_toggle_sort_order()
+                            }
+                            KeyCode::Char('h' | 'H') if can_operator_explorer => {
+                                self.script.on_toggle_hidden_dirs()
+                            }
+                            // 多选
+                            KeyCode::Char('m' | 'M') if can_operator_play_list => {
+                                self.script.on_mark_play_list()
+                            }
+                            KeyCode::Char('m' | 'M') if can_operator_explorer => {
+                                self.script.on_mark_explorer()
+                            }
+                            KeyCode::Char('v' | 'V') if can_operator_play_list => {
+                                self.script.on_play_list_range()
+                            }
+                            KeyCode::Char('v' | 'V') if can_operator_explorer => {
+                                self.script.on_explorer_range()
+                            }
+                            // 保存到播放列表
+                            KeyCode::Char('w' | 'W') if can_operator_play_list => {
+                                self.script.on_save_play_list_selection()
+                            }
+                            KeyCode::Char('w' | 'W') if can_operator_explorer => {
+                                self.script.on_save_explorer_selection()
+                            }
+                            KeyCode::Char('b' | 'B') if can_operator_play_list => {
+                                self.script.on_move_play_list_to_end()
+                            }
+                            // 只保留选中的歌曲
+                            KeyCode::Char('c' | 'C') if can_operator_play_list => {
+                                self.script.on_crop_play_list()
+                            }
                             KeyCode::Left => self.script.on_reverse(),
                             KeyCode::Right => self.script.on_forward(),
                             _ => {}
                         }
                     }
                 }
+                AppEvent::Input(Event::Mouse(mouse)) => redraw = self.on_mouse(mouse),
+                AppEvent::Input(_) | AppEvent::Tick => {}
+                AppEvent::TrackEnd(generation) => self.script.on_track_end(generation),
+                AppEvent::Loaded(media) => self.script.on_media_loaded(*media),
+                AppEvent::FileChanged => self.script.on_file_changed(),
+                AppEvent::ScanProgress(done, total) => self.script.on_scan_progress(done, total),
+                AppEvent::Scanned(mode, files) => self.script.on_scanned(mode, files),
             }
         }
         Ok(())
     }
 
+    // 在播放列表中拖动歌曲 ; 返回是否需要重绘，鼠标移动不需要重绘
+    fn on_mouse(&mut self, mouse: MouseEvent) -> bool {
+        let can_drag = !self.full_screen
+            && !self.karaoke
+            && !self.script.lyric_focus
+            && self.tab == TabMode::PlayList
+            && !self.script.search.is_active(SearchTarget::PlayList);
+        if !can_drag {
+            return false;
+        }
+        let index = self.play_list_row(mouse.column, mouse.row);
+        match (mouse.kind, index, self.drag) {
+            (MouseEventKind::Down(MouseButton::Left), Some(index), _) => {
+                self.script.play_list_index.select(Some(index));
+                self.drag = Some((index, false));
+                true
+            }
+            (MouseEventKind::Drag(MouseButton::Left), Some(index), Some((from, moved))) => {
+                let to = self.script.on_drag_play_list(from, index, !moved);
+                self.drag = Some((to, moved || to != from));
+                true
+            }
+            (MouseEventKind::Up(_), _, _) => {
+                self.drag = None;
+                false
+            }
+            _ => false,
+        }
+    }
+
+    // 鼠标所在的播放列表索引
+    fn play_list_row(&self, column: u16, row: u16) -> Option<usize> {
+        let area = self.play_list_area;
+        if column < area.x || column >= area.right() || row < area.y || row >= area.bottom() {
+            return None;
+        }
+        let index = self.script.play_list_index.offset() + (row - area.y) as usize;
+        (index < self.script.play_list.len()).then_some(index)
+    }
+
     fn draw<B>(&mut self, terminal: &mut Terminal<B>) -> Result<(), std::io::Error>
     where
         B: Backend,
     {
+        self.cover.begin_frame();
         terminal.draw(|frame| {
-            if self.full_screen {
+            if self.karaoke {
+                draw_karaoke(frame, frame.size(), &mut self.script)
+            } else if self.full_screen {
                 draw_media(
                     frame,
                     frame.size(),
@@ -195,6 +544,7 @@ impl UI {
                     Config::LAYOUT_LYRICS_WIDGET_WIDTH_ON_FULL_SCREEN,
                     Config::LAYOUT_INFO_WIDGET_WIDTH_ON_FULL_SCREEN,
                     self.show_info,
+                    &mut self.cover,
                 )
             } else {
                 let layout = Layout::default()
@@ -206,14 +556,37 @@ impl UI {
                 #[cfg(feature = "debug")]
                 let text = self.script.header_text.clone();
                 #[cfg(not(feature = "debug"))]
-                let text = match self.tab {
-                    TabMode::Explorer => Config::SHORTCUT_KEY_EXPLORER,
-                    TabMode::PlayList => Config::SHORTCUT_KEY_PLAY_LIST,
+                let text = if self.script.playlists.picker.is_some() {
+                    Config::SHORTCUT_KEY_PICKER.to_string()
+                } else if self.script.tag_editor.is_some() {
+                    Config::SHORTCUT_KEY_TAG_EDITOR.to_string()
+                } else if self.script.lyric_sync.is_some() {
+                    Config::SHORTCUT_KEY_LYRIC_SYNC.to_string()
+                } else if self.script.lyric_focus {
+                    Config::SHORTCUT_KEY_LYRICS.to_string()
+                } else {
+                    match self.tab {
+                        TabMode::Explorer => Config::SHORTCUT_KEY_EXPLORER,
+                        TabMode::PlayList => Config::SHORTCUT_KEY_PLAY_LIST,
+                        TabMode::Playlists => Config::SHORTCUT_KEY_PLAYLISTS,
+                    }
+                    .to_string()
+                        + " "
+                        + Config::SHORTCUT_KEY_COMMON
+                };
+                // 添加文件夹的进度
+                let text = match self.script.scan_progress {
+                    Some((done, total)) if total > 0 => {
+                        format!("{} {}/{}", Config::TIP_SCANNING, done, total)
+                    }
+                    Some(_) => Config::TIP_SCANNING.to_string(),
+                    None => text,
+                };
+                match (self.script.error_message(), self.script.notice_message()) {
+                    (Some(error), _) => draw_error(frame, layout[0], error),
+                    (None, Some(notice)) => draw_header(frame, layout[0], notice),
+                    (None, None) => draw_header(frame, layout[0], &text),
                 }
-                .to_string()
-                    + " "
-                    + Config::SHORTCUT_KEY_COMMON;
-                draw_header(frame, layout[0], &text);
 
                 let main_layout = Layout::default()
                     .direction(Direction::Horizontal)
@@ -221,7 +594,11 @@ impl UI {
                     .split(layout[1]);
                 match self.tab {
                     TabMode::Explorer => draw_fs(frame, main_layout[0], &mut self.script),
-                    TabMode::PlayList => draw_play_list(frame, main_layout[0], &mut self.script),
+                    TabMode::PlayList => {
+                        self.play_list_area =
+                            draw_play_list(frame, main_layout[0], &mut self.script)
+                    }
+                    TabMode::Playlists => draw_playlists(frame, main_layout[0], &mut self.script),
                 }
                 draw_media(
                     frame,
@@ -230,7 +607,9 @@ impl UI {
                     Config::LAYOUT_LYRICS_WIDGET_WIDTH,
                     Config::LAYOUT_INFO_WIDGET_WIDTH,
                     self.show_info,
+                    &mut self.cover,
                 );
+                draw_playlist_picker(frame, layout[1], &mut self.script);
             }
         })?;
         Ok(())
@@ -1,17 +1,99 @@
-use ratatui::{layout::Rect, widgets::ListItem, Frame};
+use std::cmp;
 
-use crate::{config::Config, script::Script};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{List, ListItem},
+    Frame,
+};
+
+use crate::{
+    config::Config,
+    script::{Script, SearchTarget},
+};
 
 use super::common::Common;
 
-pub fn draw_play_list(frame: &mut Frame, area: Rect, script: &mut Script) {
+// 返回列表内容的位置 ; 用于鼠标拖动
+pub fn draw_play_list(frame: &mut Frame, area: Rect, script: &mut Script) -> Rect {
+    if script.search.is_active(SearchTarget::PlayList) {
+        let block = Common::block().title(Common::search_title(
+            Config::TITLE_PLAY_LIST,
+            &script.search,
+        ));
+        let file_list = Common::search_list(&script.search).block(block);
+        frame.render_stateful_widget(file_list, area, &mut script.search.index);
+        return Rect::default();
+    }
+
+    // 有下一首播放的歌曲时，在播放列表上方展示
+    let area = if script.up_next.is_empty() {
+        area
+    } else {
+        let height = cmp::min(script.up_next.len(), Config::UP_NEXT_MAX_ROWS) as u16 + 2;
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(height), Constraint::Min(0)])
+            .split(area);
+        draw_up_next(frame, layout[0], script);
+        layout[1]
+    };
+
     let mut items = vec![];
 
-    for it in &script.play_list {
-        items.push(ListItem::new(it.media.get_name()));
+    for (i, it) in script.play_list.iter().enumerate() {
+        // 标记或范围选择中的歌曲前加上记号
+        let name = if it.marked || script.in_play_list_range(i) {
+            format!("* {}", it.media.get_name())
+        } else {
+            it.media.get_name()
+        };
+        // 已经删除的文件变暗，已经播放的歌曲变灰，正在播放的歌曲加粗
+        let style = match script.current {
+            _ if it.missing => Style::default().fg(Color::DarkGray),
+            Some(current) if i < current => Style::default().fg(Color::Gray),
+            Some(current) if i == current => Style::default()
+                .fg(Common::LIGHT_THEME)
+                .add_modifier(Modifier::BOLD),
+            _ => Style::default(),
+        };
+        if it.missing {
+            items.push(
+                ListItem::new(format!("{} ({})", name, Config::TIP_FILE_MISSING)).style(style),
+            );
+        } else {
+            items.push(ListItem::new(name).style(style));
+        }
     }
 
-    let block = Common::block().title(Config::TITLE_PLAY_LIST);
+    // 标题展示正在播放的位置 ; 例如: Play List 3/10
+    let title = match script.current {
+        Some(current) => format!(
+            "{} {}/{}",
+            Config::TITLE_PLAY_LIST,
+            current + 1,
+            script.play_list.len()
+        ),
+        None => Config::TITLE_PLAY_LIST.to_string(),
+    };
+    let block = Common::block().title(title);
+    let inner = block.inner(area);
     let file_list = Common::list(items).block(block);
     frame.render_stateful_widget(file_list, area, &mut script.play_list_index);
+    inner
+}
+
+// 下一首播放 ; 按播放顺序展示
+fn draw_up_next(frame: &mut Frame, area: Rect, script: &Script) {
+    let items: Vec<ListItem> = script
+        .up_next
+        .iter()
+        .map(|it| ListItem::new(it.media.get_name()))
+        .collect();
+    let block = Common::block().title(format!(
+        "{} ({})",
+        Config::TITLE_UP_NEXT,
+        script.up_next.len()
+    ));
+    frame.render_widget(List::new(items).block(block), area);
 }
@@ -0,0 +1,85 @@
+use std::cmp;
+
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    widgets::{Clear, ListItem},
+    Frame,
+};
+
+use crate::{
+    config::Config,
+    script::{PlaylistInput, Script},
+};
+
+use super::common::Common;
+
+// 播放列表管理 ; 正在使用的播放列表加粗
+pub fn draw_playlists(frame: &mut Frame, area: Rect, script: &mut Script) {
+    let mut items: Vec<ListItem> = script
+        .playlists
+        .names
+        .iter()
+        .map(|it| {
+            if *it == script.active_playlist {
+                ListItem::new(it.clone()).style(
+                    Style::default()
+                        .fg(Common::LIGHT_THEME)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                ListItem::new(it.clone())
+            }
+        })
+        .collect();
+    // 智能播放列表 ; 例如: jazz (smart)
+    items.extend(
+        script
+            .playlists
+            .smart
+            .iter()
+            .map(|it| ListItem::new(format!("{} ({})", it, Config::TITLE_SMART_PLAYLIST))),
+    );
+
+    // 输入名称时在标题中展示 ; 例如: Playlists rename: abc_
+    let title = match &script.playlists.input {
+        Some((kind, input)) => {
+            let label = match kind {
+                PlaylistInput::Create => Config::PLAYLIST_INPUT_NAMES[0],
+                PlaylistInput::Rename => Config::PLAYLIST_INPUT_NAMES[1],
+                PlaylistInput::Duplicate => Config::PLAYLIST_INPUT_NAMES[2],
+                PlaylistInput::Smart => Config::PLAYLIST_INPUT_NAMES[3],
+                PlaylistInput::Query => Config::PLAYLIST_INPUT_NAMES[4],
+            };
+            format!("{} {}: {}_", Config::TITLE_PLAYLISTS, label, input)
+        }
+        None => Config::TITLE_PLAYLISTS.to_string(),
+    };
+    let block = Common::block().title(title);
+    let list = Common::list(items).block(block);
+    frame.render_stateful_widget(list, area, &mut script.playlists.index);
+}
+
+// 选择要添加到的播放列表 ; 在界面中间弹出
+pub fn draw_playlist_picker(frame: &mut Frame, area: Rect, script: &mut Script) {
+    let Some((index, _)) = &mut script.playlists.picker else {
+        return;
+    };
+    let width = cmp::min(area.width, 40);
+    let height = cmp::min(area.height, script.playlists.names.len() as u16 + 2);
+    let popup = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    );
+    let items: Vec<ListItem> = script
+        .playlists
+        .names
+        .iter()
+        .map(|it| ListItem::new(it.clone()))
+        .collect();
+    let block = Common::block().title(Config::TITLE_PLAYLIST_PICKER);
+    frame.render_widget(Clear, popup);
+    frame.render_stateful_widget(Common::list(items).block(block), popup, index);
+}
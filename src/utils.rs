@@ -1,4 +1,4 @@
-use std::{fs, path::PathBuf};
+use std::{cmp::Ordering, fs, iter::Peekable, path::PathBuf, str::Chars};
 
 use regex::Regex;
 
@@ -32,3 +32,196 @@ fn inner_search_directory(path: &PathBuf, pattern: &str, items: &mut Vec<PathBuf
         }
     }
 }
+
+// base64编码
+pub fn base64(data: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(TABLE[(n >> (18 - i * 6) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+// base64解码 ; 忽略空白字符，格式错误时返回None
+pub fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let value = |c: u8| match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    };
+    let chars: Vec<u8> = text
+        .bytes()
+        .filter(|c| !c.is_ascii_whitespace())
+        .take_while(|&c| c != b'=')
+        .collect();
+    let mut out = Vec::with_capacity(chars.len() * 3 / 4);
+    for chunk in chars.chunks(4) {
+        let mut n = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            n |= (value(c)? as u32) << (18 - i * 6);
+        }
+        for i in 0..chunk.len().saturating_sub(1) {
+            out.push((n >> (16 - i * 8)) as u8);
+        }
+    }
+    Some(out)
+}
+
+// 解码文本文件 ; 优先使用BOM，其次是utf-8，最后按内容猜测编码(gbk、big5、shift-jis等)
+pub fn decode_text(data: &[u8]) -> String {
+    if let Some((encoding, bom_len)) = encoding_rs::Encoding::for_bom(data) {
+        let (text, _) = encoding.decode_without_bom_handling(&data[bom_len..]);
+        return text.into_owned();
+    }
+    if let Ok(text) = std::str::from_utf8(data) {
+        return text.to_string();
+    }
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(data, true);
+    let (text, _, _) = detector.guess(None, true).decode(data);
+    text.into_owned()
+}
+
+// 自然顺序比较 ; 数字按数值比较，"2" 在 "10" 前面，字母不区分大小写
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+    loop {
+        let ordering = match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let x = take_number(&mut a);
+                let y = take_number(&mut b);
+                // 去掉前导0后，位数多的数值大
+                let (x, y) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+                x.len().cmp(&y.len()).then_with(|| x.cmp(y))
+            }
+            (Some(x), Some(y)) => {
+                a.next();
+                b.next();
+                x.to_lowercase().cmp(y.to_lowercase())
+            }
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+fn take_number(chars: &mut Peekable<Chars>) -> String {
+    let mut number = String::new();
+    while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+        number.push(c);
+    }
+    number
+}
+
+// unix 秒转换为日期 ; 例如: 2024-03-01，使用 UTC
+pub fn format_date(secs: u64) -> String {
+    // 以 0000-03-01 为起点计算，闰日在每年的最后
+    let days = secs / 86400 + 719468;
+    let era = days / 146097;
+    let day_of_era = days % 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// 测试用的临时文件路径 ; 每个测试使用不同的名称
+#[cfg(test)]
+pub fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("rust-player-{}-{}", std::process::id(), name))
+}
+
+// 写入测试用的临时文件
+#[cfg(test)]
+pub fn temp_file(name: &str, data: &[u8]) -> PathBuf {
+    let path = temp_path(name);
+    fs::write(&path, data).unwrap();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_compare_by_value() {
+        assert_eq!(natural_cmp("track 2", "track 10"), Ordering::Less);
+        assert_eq!(natural_cmp("track 10", "track 9"), Ordering::Greater);
+        assert_eq!(natural_cmp("1 a", "1 b"), Ordering::Less);
+        assert_eq!(natural_cmp("a", "a1"), Ordering::Less);
+    }
+
+    #[test]
+    fn leading_zeros_are_ignored() {
+        assert_eq!(natural_cmp("02", "10"), Ordering::Less);
+        assert_eq!(natural_cmp("007", "7"), Ordering::Equal);
+        assert_eq!(natural_cmp("0010", "9"), Ordering::Greater);
+        assert_eq!(natural_cmp("0", "000"), Ordering::Equal);
+    }
+
+    #[test]
+    fn digit_runs_longer_than_u64() {
+        let big = "99999999999999999999999999";
+        let bigger = "100000000000000000000000000";
+        assert_eq!(natural_cmp(big, bigger), Ordering::Less);
+        assert_eq!(
+            natural_cmp(&format!("x{}y", big), &format!("x{}z", big)),
+            Ordering::Less
+        );
+        assert_eq!(
+            natural_cmp(&format!("0{}", bigger), bigger),
+            Ordering::Equal
+        );
+    }
+
+    #[test]
+    fn letters_ignore_case() {
+        assert_eq!(natural_cmp("abc", "ABC"), Ordering::Equal);
+        assert_eq!(natural_cmp("a", "B"), Ordering::Less);
+        assert_eq!(natural_cmp("Zebra", "apple"), Ordering::Greater);
+    }
+
+    #[test]
+    fn non_ascii() {
+        assert_eq!(natural_cmp("Éclair", "éclair"), Ordering::Equal);
+        assert_eq!(natural_cmp("歌曲 2", "歌曲 10"), Ordering::Less);
+        // 全角数字不按数值比较
+        assert_eq!(natural_cmp("２", "１０"), Ordering::Greater);
+        assert_eq!(natural_cmp("Äpfel 3", "äpfel 03"), Ordering::Equal);
+    }
+
+    #[test]
+    fn format_date_in_utc() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(86399), "1970-01-01");
+        assert_eq!(format_date(951782400), "2000-02-29");
+        assert_eq!(format_date(951868800), "2000-03-01");
+        assert_eq!(format_date(1709251199), "2024-02-29");
+        assert_eq!(format_date(4102444800), "2100-01-01");
+    }
+}